@@ -0,0 +1,507 @@
+pub mod bigint {
+    use std::{cmp::Ordering, fmt, ops::{Add, Div, Mul, Neg, Rem, Sub}, str::FromStr};
+
+    //---- BigInt ----//
+    // A signed integer of any size: a sign and little-endian 32-bit limbs without trailing zeros
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub struct BigInt {
+        negative: bool,
+        magnitude: Vec<u32>,
+    }
+
+    impl BigInt {
+        pub fn zero() -> Self {
+            Self { negative: false, magnitude: vec![] }
+        }
+
+        pub fn one() -> Self {
+            Self::from(1)
+        }
+
+        pub fn is_zero(&self) -> bool {
+            self.magnitude.is_empty()
+        }
+
+        pub fn is_one(&self) -> bool {
+            !self.negative && self.magnitude == [1]
+        }
+
+        pub fn is_positive(&self) -> bool {
+            !self.negative && !self.is_zero()
+        }
+
+        pub fn is_negative(&self) -> bool {
+            self.negative
+        }
+
+        pub fn signum(&self) -> i32 {
+            if self.negative { -1 } else if self.is_zero() { 0 } else { 1 }
+        }
+
+        pub fn abs(&self) -> Self {
+            Self { negative: false, magnitude: self.magnitude.clone() }
+        }
+
+        pub fn bits(&self) -> u64 {
+            match self.magnitude.last() {
+                Some(top) => 32 * self.magnitude.len() as u64 - u64::from(top.leading_zeros()),
+                None => 0,
+            }
+        }
+
+        pub fn pow(&self, exponent: u32) -> Self {
+            let mut result = Self::one();
+            let mut base = self.clone();
+            let mut exponent = exponent;
+            while exponent > 0 {
+                if exponent & 1 == 1 {
+                    result = &result * &base;
+                }
+                base = &base * &base;
+                exponent >>= 1;
+            }
+            result
+        }
+
+        // Quotient truncated toward zero, and the remainder with the sign of `self`
+        pub fn div_rem(&self, other: &Self) -> (Self, Self) {
+            if other.is_zero() {
+                panic!("big integer division by zero");
+            }
+
+            let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &other.magnitude);
+            (
+                Self::from_parts(self.negative != other.negative, quotient),
+                Self::from_parts(self.negative, remainder),
+            )
+        }
+
+        pub fn gcd(&self, other: &Self) -> Self {
+            let (mut a, mut b) = (self.abs(), other.abs());
+            while !b.is_zero() {
+                let remainder = a.div_rem(&b).1;
+                a = b;
+                b = remainder;
+            }
+            a
+        }
+
+        // The exact square root, when there is one
+        pub fn sqrt(&self) -> Option<Self> {
+            if self.negative {
+                return None;
+            }
+            if self.is_zero() {
+                return Some(Self::zero());
+            }
+
+            // Newton's iteration from above converges to the floor of the root
+            let two = Self::from(2);
+            let mut root = Self::one().shifted_left(self.bits().div_ceil(2));
+            loop {
+                let next = &(&root + &(self / &root)) / &two;
+                if next >= root {
+                    break;
+                }
+                root = next;
+            }
+            (&root * &root == *self).then_some(root)
+        }
+
+        pub fn to_i64(&self) -> Option<i64> {
+            if self.magnitude.len() > 2 {
+                return None;
+            }
+
+            let magnitude = self.magnitude.iter().rev().fold(0u64, |acc, &limb| (acc << 32) | u64::from(limb));
+            if self.negative {
+                0i64.checked_sub_unsigned(magnitude)
+            }
+            else {
+                i64::try_from(magnitude).ok()
+            }
+        }
+
+        pub fn to_f64(&self) -> f64 {
+            let magnitude = self.magnitude.iter().rev().fold(0.0, |acc, &limb| acc * 4294967296.0 + f64::from(limb));
+            if self.negative { -magnitude } else { magnitude }
+        }
+
+        pub fn shifted_left(&self, bits: u64) -> Self {
+            Self::from_parts(self.negative, shift_left(&self.magnitude, bits))
+        }
+
+        pub fn shifted_right(&self, bits: u64) -> Self {
+            let limbs = (bits / 32) as usize;
+            let offset = bits % 32;
+            if limbs >= self.magnitude.len() {
+                return Self::zero();
+            }
+
+            let mut magnitude: Vec<u32> = self.magnitude[limbs..].to_vec();
+            if offset > 0 {
+                for i in 0..magnitude.len() {
+                    let high = magnitude.get(i + 1).map_or(0, |&limb| limb << (32 - offset));
+                    magnitude[i] = (magnitude[i] >> offset) | high;
+                }
+            }
+            Self::from_parts(self.negative, magnitude)
+        }
+
+        fn from_parts(negative: bool, magnitude: Vec<u32>) -> Self {
+            let magnitude = trimmed(magnitude);
+            Self { negative: negative && !magnitude.is_empty(), magnitude }
+        }
+    }
+
+    impl From<i64> for BigInt {
+        fn from(value: i64) -> Self {
+            let magnitude = value.unsigned_abs();
+            Self::from_parts(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+        }
+    }
+
+    impl From<i32> for BigInt {
+        fn from(value: i32) -> Self {
+            Self::from(i64::from(value))
+        }
+    }
+
+    impl fmt::Display for BigInt {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            if self.is_zero() {
+                return write!(f, "0");
+            }
+
+            // peel off nine decimal digits at a time
+            let mut chunks: Vec<u32> = vec![];
+            let mut magnitude = self.magnitude.clone();
+            while !magnitude.is_empty() {
+                let (quotient, remainder) = div_rem_magnitude(&magnitude, &[1_000_000_000]);
+                chunks.push(remainder.first().copied().unwrap_or(0));
+                magnitude = quotient;
+            }
+
+            let mut s = if self.negative { "-".to_string() } else { String::new() };
+            s += &chunks.pop().unwrap().to_string();
+            for chunk in chunks.iter().rev() {
+                s += &format!("{:09}", chunk);
+            }
+            write!(f, "{}", s)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ParseBigIntError;
+
+    impl FromStr for BigInt {
+        type Err = ParseBigIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, s),
+            };
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(ParseBigIntError);
+            }
+
+            let ten = Self::from(10);
+            let value = digits.chars().fold(Self::zero(), |acc, c| &(&acc * &ten) + &Self::from(c.to_digit(10).unwrap() as i32));
+            Ok(if negative { -value } else { value })
+        }
+    }
+    ////// BigInt //////
+
+
+    //---- Arithmetic ----//
+    impl Add for &BigInt {
+        type Output = BigInt;
+
+        fn add(self, other: &BigInt) -> BigInt {
+            if self.negative == other.negative {
+                return BigInt::from_parts(self.negative, add_magnitude(&self.magnitude, &other.magnitude));
+            }
+
+            // opposite signs subtract the smaller magnitude from the larger
+            match compare_magnitude(&self.magnitude, &other.magnitude) {
+                Ordering::Less => BigInt::from_parts(other.negative, sub_magnitude(&other.magnitude, &self.magnitude)),
+                _ => BigInt::from_parts(self.negative, sub_magnitude(&self.magnitude, &other.magnitude)),
+            }
+        }
+    }
+
+    impl Sub for &BigInt {
+        type Output = BigInt;
+
+        fn sub(self, other: &BigInt) -> BigInt {
+            self + &-other
+        }
+    }
+
+    impl Mul for &BigInt {
+        type Output = BigInt;
+
+        fn mul(self, other: &BigInt) -> BigInt {
+            BigInt::from_parts(self.negative != other.negative, mul_magnitude(&self.magnitude, &other.magnitude))
+        }
+    }
+
+    impl Div for &BigInt {
+        type Output = BigInt;
+
+        fn div(self, other: &BigInt) -> BigInt {
+            self.div_rem(other).0
+        }
+    }
+
+    impl Rem for &BigInt {
+        type Output = BigInt;
+
+        fn rem(self, other: &BigInt) -> BigInt {
+            self.div_rem(other).1
+        }
+    }
+
+    impl Neg for &BigInt {
+        type Output = BigInt;
+
+        fn neg(self) -> BigInt {
+            BigInt::from_parts(!self.negative, self.magnitude.clone())
+        }
+    }
+
+    impl Neg for BigInt {
+        type Output = BigInt;
+
+        fn neg(self) -> BigInt {
+            BigInt::from_parts(!self.negative, self.magnitude)
+        }
+    }
+
+    impl PartialOrd for BigInt {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for BigInt {
+        fn cmp(&self, other: &Self) -> Ordering {
+            match (self.negative, other.negative) {
+                (false, true) => Ordering::Greater,
+                (true, false) => Ordering::Less,
+                (false, false) => compare_magnitude(&self.magnitude, &other.magnitude),
+                (true, true) => compare_magnitude(&other.magnitude, &self.magnitude),
+            }
+        }
+    }
+
+    fn compare_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+        a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+    }
+
+    fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+        let mut carry = 0u64;
+        for i in 0..a.len().max(b.len()) {
+            let total = u64::from(*a.get(i).unwrap_or(&0)) + u64::from(*b.get(i).unwrap_or(&0)) + carry;
+            sum.push(total as u32);
+            carry = total >> 32;
+        }
+        sum.push(carry as u32);
+        sum
+    }
+
+    // a - b for a >= b
+    fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut difference = Vec::with_capacity(a.len());
+        let mut borrow = 0i64;
+        for (i, &limb) in a.iter().enumerate() {
+            let mut total = i64::from(limb) - i64::from(*b.get(i).unwrap_or(&0)) - borrow;
+            borrow = 0;
+            if total < 0 {
+                total += 1 << 32;
+                borrow = 1;
+            }
+            difference.push(total as u32);
+        }
+        difference
+    }
+
+    fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut product = vec![0u32; a.len() + b.len()];
+        for (i, &x) in a.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &y) in b.iter().enumerate() {
+                let total = u64::from(x) * u64::from(y) + u64::from(product[i + j]) + carry;
+                product[i + j] = total as u32;
+                carry = total >> 32;
+            }
+            product[i + b.len()] = carry as u32;
+        }
+        product
+    }
+
+    fn shift_left(a: &[u32], bits: u64) -> Vec<u32> {
+        let offset = bits % 32;
+        let mut shifted = vec![0u32; (bits / 32) as usize];
+        let mut carry = 0u32;
+        for &limb in a {
+            if offset == 0 {
+                shifted.push(limb);
+            }
+            else {
+                shifted.push((limb << offset) | carry);
+                carry = limb >> (32 - offset);
+            }
+        }
+        shifted.push(carry);
+        shifted
+    }
+
+    fn div_rem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+        if compare_magnitude(a, b) == Ordering::Less {
+            return (vec![], a.to_vec());
+        }
+
+        // a single limb divisor only needs short division
+        if b.len() == 1 {
+            let divisor = u64::from(b[0]);
+            let mut quotient = vec![0u32; a.len()];
+            let mut remainder = 0u64;
+            for i in (0..a.len()).rev() {
+                let current = (remainder << 32) | u64::from(a[i]);
+                quotient[i] = (current / divisor) as u32;
+                remainder = current % divisor;
+            }
+            return (trimmed(quotient), trimmed(vec![remainder as u32]));
+        }
+
+        // Otherwise Knuth's algorithm D: with the divisor shifted until its top bit is set, each
+        // quotient limb is estimated from the top limbs of the remainder, which is at most two too
+        // large after the first correction and one too large after the second
+        let shift = u64::from(b.last().unwrap().leading_zeros());
+        let divisor = trimmed(shift_left(b, shift));
+        let mut remainder = shift_left(a, shift);
+        let n = divisor.len();
+        let (top, second) = (u64::from(divisor[n - 1]), u64::from(divisor[n - 2]));
+
+        let mut quotient = vec![0u32; remainder.len() - n];
+        for j in (0..quotient.len()).rev() {
+            let leading = (u64::from(remainder[j + n]) << 32) | u64::from(remainder[j + n - 1]);
+            let mut estimate = leading / top;
+            let mut rest = leading % top;
+            while estimate > u64::from(u32::MAX) || estimate * second > ((rest << 32) | u64::from(remainder[j + n - 2])) {
+                estimate -= 1;
+                rest += top;
+                if rest > u64::from(u32::MAX) {
+                    break;
+                }
+            }
+
+            // subtract estimate * divisor in place
+            let mut borrow = 0i64;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let product = estimate * u64::from(divisor[i]) + carry;
+                carry = product >> 32;
+                let total = i64::from(remainder[i + j]) - borrow - (product & u64::from(u32::MAX)) as i64;
+                remainder[i + j] = total as u32;
+                borrow = i64::from(total < 0);
+            }
+            let total = i64::from(remainder[j + n]) - borrow - carry as i64;
+            remainder[j + n] = total as u32;
+
+            // the estimate was one too large, so the divisor goes back once
+            if total < 0 {
+                estimate -= 1;
+                let mut carry = 0u64;
+                for i in 0..n {
+                    let sum = u64::from(remainder[i + j]) + u64::from(divisor[i]) + carry;
+                    remainder[i + j] = sum as u32;
+                    carry = sum >> 32;
+                }
+                remainder[j + n] = remainder[j + n].wrapping_add(carry as u32);
+            }
+            quotient[j] = estimate as u32;
+        }
+
+        remainder.truncate(n);
+        (trimmed(quotient), BigInt::from_parts(false, remainder).shifted_right(shift).magnitude)
+    }
+
+    fn trimmed(mut magnitude: Vec<u32>) -> Vec<u32> {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        magnitude
+    }
+    ////// Arithmetic //////
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn big(s: &str) -> BigInt {
+            s.parse().unwrap()
+        }
+
+        #[test]
+        fn div_rem_truncates_toward_zero() {
+            for (a, b, quotient, remainder) in [(7, 2, 3, 1), (-7, 2, -3, -1), (7, -2, -3, 1), (-7, -2, 3, -1), (6, 3, 2, 0), (2, 7, 0, 2)] {
+                assert_eq!(BigInt::from(a).div_rem(&BigInt::from(b)), (BigInt::from(quotient), BigInt::from(remainder)));
+            }
+            assert_eq!(big("-100000000000000000000000000001").div_rem(&big("100000000000000000000")), (big("-1000000000"), big("-1")));
+        }
+
+        #[test]
+        fn div_rem_by_multi_limb_divisors() {
+            // limbs that are all ones or only the top bit are where the quotient estimates are off
+            let limbs = [0, 1, 2, 0x7fff_ffff, 0x8000_0000, 0x8000_0001, 0xffff_fffe, 0xffff_ffff, 0x1234_5678];
+            let number = |seed: usize, length: usize| BigInt::from_parts(false, (0..length).map(|i| limbs[(seed * 7 + i * 5 + i * i) % limbs.len()]).collect());
+
+            for seed in 0..40 {
+                for (a_length, b_length) in [(2, 2), (3, 2), (4, 2), (5, 3), (6, 4), (8, 3), (9, 5)] {
+                    let (a, b) = (number(seed, a_length), number(seed + 3, b_length));
+                    if b.is_zero() {
+                        continue;
+                    }
+                    let (quotient, remainder) = a.div_rem(&b);
+                    assert_eq!(&(&quotient * &b) + &remainder, a);
+                    assert!(!remainder.is_negative() && remainder < b.abs());
+                }
+            }
+
+            let a = BigInt::from_parts(false, vec![0, 0, 0x8000_0000, 0x7fff_ffff]);
+            let b = BigInt::from_parts(false, vec![1, 0, 0x8000_0000]);
+            assert_eq!(a.div_rem(&b), (BigInt::from(4294967294_i64), big("39614081257132168792477007874")));
+        }
+
+        #[test]
+        fn mul_carries_across_limbs() {
+            assert_eq!(&BigInt::from(4294967295_i64) * &BigInt::from(4294967295_i64), big("18446744065119617025"));
+            assert_eq!(&big("123456789012345678901234567890") * &big("-987654321098765432109876543210"), big("-121932631137021795226185032733622923332237463801111263526900"));
+            assert_eq!(&big("-5") * &BigInt::zero(), BigInt::zero());
+        }
+
+        #[test]
+        fn conversions() {
+            assert_eq!(BigInt::from(i64::MIN).to_i64(), Some(i64::MIN));
+            assert_eq!(BigInt::from(i64::MAX).to_i64(), Some(i64::MAX));
+            assert_eq!((&BigInt::from(i64::MAX) + &BigInt::one()).to_i64(), None);
+            assert_eq!((&BigInt::from(i64::MIN) - &BigInt::one()).to_i64(), None);
+            assert_eq!(BigInt::one().shifted_left(70).to_f64(), 2f64.powi(70));
+            assert_eq!(BigInt::from(-12345).to_f64(), -12345.0);
+        }
+
+        #[test]
+        fn sqrt_is_exact_or_none() {
+            assert_eq!(big("10000000000000000000000000000000000000000").sqrt(), Some(big("100000000000000000000")));
+            assert_eq!(big("10000000000000000000000000000000000000001").sqrt(), None);
+            assert_eq!(BigInt::zero().sqrt(), Some(BigInt::zero()));
+            assert_eq!(BigInt::from(2).sqrt(), None);
+            assert_eq!(BigInt::from(-4).sqrt(), None);
+        }
+    }
+}
@@ -0,0 +1,611 @@
+pub mod canonical {
+    use std::{cmp::Ordering, collections::{BTreeMap, BTreeSet}};
+    use crate::rational::rational::Rational;
+    use crate::entity::entity::{as_number, create_number, create_variable, CompositionFunction, ConstantTerm, DivisionFunction, Expr, MultiplicationFunction, PowerFunction, SummationFunction, VariableEntity, VariableIdentifier, VariableTerm};
+
+    //---- Terms ----//
+    type Powers<K> = BTreeMap<K, Rational>;
+    pub type PowerList<K> = Vec<(K, Rational)>;
+
+    // c * x1^e1 * ... * xn^en * f1^k1 * ... * fm^km, where the f are canonical factors that are not variables
+    #[derive(Clone)]
+    struct Term {
+        coefficient: Rational,
+        variables: Powers<VariableIdentifier>,
+        factors: Powers<Expr>,
+    }
+
+    impl Term {
+        fn constant(coefficient: Rational) -> Self {
+            Self { coefficient, variables: BTreeMap::new(), factors: BTreeMap::new() }
+        }
+
+        fn factor(factor: Expr, power: Rational) -> Self {
+            let mut term = Self::constant(Rational::one());
+            add_power(&mut term.factors, factor, &power);
+            term
+        }
+
+        fn multiply(mut self, other: Term) -> Self {
+            self.coefficient = &self.coefficient * &other.coefficient;
+            for (variable, power) in other.variables {
+                add_power(&mut self.variables, variable, &power);
+            }
+            for (factor, power) in other.factors {
+                add_power(&mut self.factors, factor, &power);
+            }
+            self
+        }
+
+        // every power scaled by `exponent`, when the coefficient can be raised to it exactly and
+        // the powers can be multiplied without changing the value for negative bases
+        fn pow(&self, exponent: &Rational) -> Option<Self> {
+            if !exponent.is_integer() && !self.scales_by(exponent) {
+                return None;
+            }
+
+            let coefficient = if self.coefficient == Rational::one() {
+                Rational::one()
+            }
+            else {
+                let exponent = exponent.to_i64().filter(|e| e.abs() <= 64)? as i32;
+                if self.coefficient.is_zero() && exponent < 0 {
+                    return None;
+                }
+                self.coefficient.pow(exponent)
+            };
+
+            Some(Self { coefficient, variables: scaled(&self.variables, exponent), factors: scaled(&self.factors, exponent) })
+        }
+
+        // Whether (b^p)^e = b^(pe) for the one base b of the term when e is not an integer. For a
+        // negative b, an even p makes the left side defined and the right side not, as in
+        // (x^2)^(1/2) = |x|, and a pe that is an integer makes only the right side defined
+        fn scales_by(&self, exponent: &Rational) -> bool {
+            let powers: Vec<&Rational> = self.variables.values().chain(self.factors.values()).collect();
+            match powers[..] {
+                [] => true,
+                [power] => power.to_i64().is_none_or(|p| p % 2 != 0) && !(power * exponent).is_integer(),
+                _ => false,
+            }
+        }
+
+        fn degree(&self) -> Rational {
+            self.variables.values().fold(Rational::zero(), |degree, power| &degree + power)
+        }
+
+        fn to_expr(&self) -> Expr {
+            let (numerator_variables, denominator_variables) = split_powers(&self.variables);
+            let (numerator_factors, denominator_factors) = split_powers(&self.factors);
+
+            if denominator_variables.is_empty() && denominator_factors.is_empty() {
+                return monomial(self.coefficient.clone(), numerator_variables, numerator_factors);
+            }
+
+            // the coefficient's denominator goes below the line with the negative powers, as in 1/(3x)
+            let numerator = monomial(self.coefficient.numerator().clone().into(), numerator_variables, numerator_factors);
+            let denominator = monomial(self.coefficient.denominator().clone().into(), denominator_variables, denominator_factors);
+            DivisionFunction::new(numerator, denominator).into()
+        }
+    }
+
+    fn add_power<K: Ord>(powers: &mut Powers<K>, key: K, power: &Rational) {
+        let sum = match powers.get(&key) {
+            Some(existing) => existing + power,
+            None => power.clone(),
+        };
+
+        if sum.is_zero() {
+            powers.remove(&key);
+        }
+        else {
+            powers.insert(key, sum);
+        }
+    }
+
+    fn scaled<K: Ord + Clone>(powers: &Powers<K>, exponent: &Rational) -> Powers<K> {
+        powers.iter().map(|(key, power)| (key.clone(), power * exponent)).filter(|(_, power)| !power.is_zero()).collect()
+    }
+
+    // the positive powers and the negated negative ones
+    fn split_powers<K: Clone>(powers: &Powers<K>) -> (PowerList<K>, PowerList<K>) {
+        let (positive, negative): (Vec<_>, Vec<_>) = powers.iter().map(|(key, power)| (key.clone(), power.clone())).partition(|(_, power)| power.is_positive());
+        (positive, negative.into_iter().map(|(key, power)| (key, -power)).collect())
+    }
+
+    fn monomial(coefficient: Rational, variables: PowerList<VariableIdentifier>, factors: PowerList<Expr>) -> Expr {
+        let mut variables: Vec<VariableEntity> = variables.into_iter().map(|(variable, power)| VariableEntity::new(variable, power)).collect();
+        let product = factors.into_iter().map(|(factor, power)| raised(factor, power)).reduce(|product, factor| MultiplicationFunction::new(product, factor).into());
+
+        if variables.is_empty() {
+            return match product {
+                None => create_number(coefficient),
+                Some(product) if coefficient == Rational::one() => product,
+                Some(product) => MultiplicationFunction::new(create_number(coefficient), product).into(),
+            };
+        }
+
+        // the first variable leads the term and the others ride along with the coefficient, while
+        // the other factors stay out of the coeffs, which differentiation takes to be constant
+        let variable = variables.remove(0);
+        let term = VariableTerm::new(variable, vec![ConstantTerm::new(coefficient, variables).into()]).into();
+        match product {
+            None => term,
+            Some(product) => MultiplicationFunction::new(term, product).into(),
+        }
+    }
+
+    fn raised(factor: Expr, power: Rational) -> Expr {
+        if power == Rational::one() {
+            factor
+        }
+        else {
+            PowerFunction::new(factor, create_number(power)).into()
+        }
+    }
+    ////// Terms //////
+
+
+    //---- Ordering ----//
+    // Graded lexicographic order: higher total degree first, then higher powers of the earlier
+    // variables. Terms that only differ in their other factors keep the constant term last
+    fn graded_lex(first: &Term, second: &Term) -> Ordering {
+        second.degree().cmp(&first.degree())
+            .then_with(|| lex(first, second))
+            .then_with(|| first.factors.is_empty().cmp(&second.factors.is_empty()))
+            .then_with(|| first.factors.cmp(&second.factors))
+    }
+
+    fn lex(first: &Term, second: &Term) -> Ordering {
+        let variables: BTreeSet<&VariableIdentifier> = first.variables.keys().chain(second.variables.keys()).collect();
+        let zero = Rational::zero();
+
+        for variable in variables {
+            let a = first.variables.get(variable).unwrap_or(&zero);
+            let b = second.variables.get(variable).unwrap_or(&zero);
+            if a != b {
+                return b.cmp(a);
+            }
+        }
+        Ordering::Equal
+    }
+    ////// Ordering //////
+
+
+    //---- Canonical Form ----//
+    fn term(expr: &Expr) -> Term {
+        match expr {
+            Expr::Constant(constant) => {
+                let mut term = Term::constant(constant.value().clone());
+                for var in constant.non_wrt_variables() {
+                    add_power(&mut term.variables, var.variable().clone(), var.power());
+                }
+                term
+            },
+            Expr::Variable(variable) => {
+                let mut term = Term::constant(Rational::one());
+                add_power(&mut term.variables, variable.variable().variable().clone(), variable.variable().power());
+                variable.coeffs().iter().fold(term, |term, coeff| term.multiply(self::term(coeff)))
+            },
+            Expr::Product(product) => term(product.first()).multiply(term(product.second())),
+            Expr::Quotient(quotient) => {
+                match term(quotient.denominator()).pow(&-Rational::one()) {
+                    Some(reciprocal) => term(quotient.numerator()).multiply(reciprocal),
+                    None => {
+                        let quotient = DivisionFunction::new(canonical(quotient.numerator()), canonical(quotient.denominator()));
+                        Term::factor(quotient.into(), Rational::one())
+                    },
+                }
+            },
+            Expr::Power(power) => {
+                let Some(exponent) = as_number(power.exponent()) else {
+                    let power = PowerFunction::new(canonical(power.base()), canonical(power.exponent()));
+                    return Term::factor(power.into(), Rational::one());
+                };
+
+                let base = term(power.base());
+                match base.pow(&exponent) {
+                    Some(term) => term,
+                    None => Term::factor(base.to_expr(), exponent),
+                }
+            },
+            Expr::Sum(_) => {
+                let mut terms = sum_terms(expr);
+                match terms.len() {
+                    0 => Term::constant(Rational::zero()),
+                    1 => terms.remove(0),
+                    _ => Term::factor(sum_expr(&terms), Rational::one()),
+                }
+            },
+            Expr::Composition(composition) => {
+                let composition = CompositionFunction::new(composition.outer().clone(), canonical(composition.inner()));
+                Term::factor(composition.into(), Rational::one())
+            },
+            Expr::Named(_) => Term::factor(expr.clone(), Rational::one()),
+        }
+    }
+
+    // the terms of a flattened sum, with like terms merged, in graded lexicographic order
+    fn sum_terms(expr: &Expr) -> Vec<Term> {
+        let mut terms = Vec::new();
+        collect_terms(expr, &Rational::one(), &mut terms);
+        merged(terms)
+    }
+
+    fn collect_terms(expr: &Expr, scale: &Rational, terms: &mut Vec<Term>) {
+        if let Expr::Sum(sum) = expr {
+            for term in sum.terms() {
+                collect_terms(term, scale, terms);
+            }
+            return;
+        }
+
+        let mut term = term(expr);
+
+        // a number times a sum is spread over its terms, e.g. 2(x + y) = 2x + 2y
+        let spread = match term.factors.first_key_value() {
+            Some((sum @ Expr::Sum(_), power)) if term.variables.is_empty() && term.factors.len() == 1 && *power == Rational::one() => Some(sum),
+            _ => None,
+        };
+        if let Some(sum) = spread {
+            collect_terms(sum, &(scale * &term.coefficient), terms);
+            return;
+        }
+
+        term.coefficient = scale * &term.coefficient;
+        terms.push(term);
+    }
+
+    fn merged(terms: Vec<Term>) -> Vec<Term> {
+        let mut like: BTreeMap<(Powers<VariableIdentifier>, Powers<Expr>), Rational> = BTreeMap::new();
+        for term in terms {
+            let entry = like.entry((term.variables, term.factors)).or_insert_with(Rational::zero);
+            *entry = &*entry + &term.coefficient;
+        }
+
+        let mut terms: Vec<Term> = like
+            .into_iter()
+            .filter(|(_, coefficient)| !coefficient.is_zero())
+            .map(|((variables, factors), coefficient)| Term { coefficient, variables, factors })
+            .collect();
+        terms.sort_by(graded_lex);
+        terms
+    }
+
+    fn sum_expr(terms: &[Term]) -> Expr {
+        let mut terms: Vec<Expr> = terms.iter().map(Term::to_expr).collect();
+        match terms.len() {
+            0 => create_number(0),
+            1 => terms.remove(0),
+            _ => SummationFunction::new(terms).into(),
+        }
+    }
+
+    fn canonical(expr: &Expr) -> Expr {
+        sum_expr(&sum_terms(expr))
+    }
+
+    // The canonical form of `expr`: sums and products flattened, like terms and factors merged,
+    // constants folded and terms sorted in graded lexicographic order, so that equal polynomials
+    // print the same
+    pub fn canonicalize(expr: &Expr) -> Expr {
+        let mut collapsed = expr.clone();
+        collapsed.collapse();
+        canonical(&collapsed)
+    }
+    ////// Canonical Form //////
+
+
+    //---- Expansion ----//
+    fn expanded(expr: &Expr) -> Vec<Term> {
+        match expr {
+            Expr::Sum(sum) => merged(sum.terms().iter().flat_map(expanded).collect()),
+            Expr::Variable(variable) => {
+                let mut leading = Term::constant(Rational::one());
+                add_power(&mut leading.variables, variable.variable().variable().clone(), variable.variable().power());
+                variable.coeffs().iter().fold(vec![leading], |terms, coeff| distribute(&terms, &expanded(coeff)))
+            },
+            Expr::Product(product) => distribute(&expanded(product.first()), &expanded(product.second())),
+            Expr::Quotient(quotient) => {
+                // the denominator is left factored, only the numerator is spread over it
+                let denominator = term(quotient.denominator());
+                let reciprocal = denominator.pow(&-Rational::one()).unwrap_or_else(|| Term::factor(denominator.to_expr(), -Rational::one()));
+                distribute(&expanded(quotient.numerator()), &[reciprocal])
+            },
+            Expr::Power(power) => match as_number(power.exponent()) {
+                Some(exponent) => expanded_power(power.base(), &exponent),
+                None => vec![Term::factor(PowerFunction::new(expand_terms(power.base()), expand_terms(power.exponent())).into(), Rational::one())],
+            },
+            Expr::Composition(composition) => {
+                let composition = CompositionFunction::new(composition.outer().clone(), expand_terms(composition.inner()));
+                vec![Term::factor(composition.into(), Rational::one())]
+            },
+            Expr::Constant(_) | Expr::Named(_) => sum_terms(expr),
+        }
+    }
+
+    // every product of a term of `first` with a term of `second`
+    fn distribute(first: &[Term], second: &[Term]) -> Vec<Term> {
+        let products = first.iter().flat_map(|a| second.iter().map(move |b| a.clone().multiply(b.clone())));
+        merged(products.collect())
+    }
+
+    fn expanded_power(base: &Expr, exponent: &Rational) -> Vec<Term> {
+        let terms = expanded(base);
+        if terms.len() == 1 {
+            return match terms[0].pow(exponent) {
+                Some(term) => vec![term],
+                None => vec![Term::factor(terms[0].to_expr(), exponent.clone())],
+            };
+        }
+
+        // only sums raised to natural powers are multiplied out, 1/(x + 1) stays as it is
+        match exponent.to_i64().and_then(|exponent| u32::try_from(exponent).ok()) {
+            Some(exponent) => binomial(&terms, exponent),
+            None => vec![Term::factor(sum_expr(&terms), exponent.clone())],
+        }
+    }
+
+    // (a + rest)^n = sum over k of C(n, k) * a^k * rest^(n - k), recursing into rest
+    fn binomial(terms: &[Term], exponent: u32) -> Vec<Term> {
+        let Some((first, rest)) = terms.split_first() else {
+            return vec![Term::constant(if exponent == 0 { Rational::one() } else { Rational::zero() })];
+        };
+        if rest.is_empty() {
+            let power = first.pow(&Rational::from(exponent as i64));
+            return vec![power.unwrap_or_else(|| Term::factor(first.to_expr(), Rational::from(exponent as i64)))];
+        }
+
+        let mut expansion = Vec::new();
+        let mut coefficient = Rational::one();
+        for k in 0..=exponent {
+            let power = binomial(std::slice::from_ref(first), k);
+            let remainder = binomial(rest, exponent - k);
+            for mut term in distribute(&power, &remainder) {
+                term.coefficient = &term.coefficient * &coefficient;
+                expansion.push(term);
+            }
+            coefficient = &(&coefficient * &Rational::from((exponent - k) as i64)) / &Rational::from((k + 1) as i64);
+        }
+        merged(expansion)
+    }
+
+    fn expand_terms(expr: &Expr) -> Expr {
+        sum_expr(&expanded(expr))
+    }
+
+    // The expanded form of `expr`: products distributed over sums and natural powers of sums
+    // multiplied out with binomial coefficients, as a canonical sum
+    pub fn expand(expr: &Expr) -> Expr {
+        let mut collapsed = expr.clone();
+        collapsed.collapse();
+        expand_terms(&collapsed)
+    }
+    ////// Expansion //////
+
+
+    //---- Collection ----//
+    // a_n * var^n for the terms of the expansion that share the power n of `var`
+    fn collected(variable: &VariableIdentifier, power: Rational, terms: Vec<Term>) -> Vec<Expr> {
+        if power.is_zero() {
+            return terms.iter().map(Term::to_expr).collect();
+        }
+
+        let mut leading = Term::constant(Rational::one());
+        add_power(&mut leading.variables, variable.clone(), &power);
+
+        // every coefficient goes before its power of `var`, as in 3x^2, c*x^2 or (a + b)*x^2
+        let expr = match terms.as_slice() {
+            [term] if term.variables.is_empty() && term.factors.is_empty() => term.clone().multiply(leading).to_expr(),
+            [term] if power.is_positive() && term.variables.values().all(Rational::is_positive) && term.factors.values().all(Rational::is_positive) => {
+                MultiplicationFunction::new(term.to_expr(), leading.to_expr()).into()
+            },
+            [term] => term.clone().multiply(leading).to_expr(),
+            _ if power.is_negative() => DivisionFunction::new(sum_expr(&terms), leading.pow(&-Rational::one()).unwrap().to_expr()).into(),
+            _ => MultiplicationFunction::new(sum_expr(&terms), leading.to_expr()).into(),
+        };
+        vec![expr]
+    }
+
+    // `expr` expanded and regrouped as a_n * var^n + ... + a_0, from the highest power of `var`
+    // down, where each a_k is an expression in the other variables
+    pub fn collect(expr: &Expr, variable: &VariableIdentifier) -> Expr {
+        let mut collapsed = expr.clone();
+        collapsed.collapse();
+
+        let mut by_power: BTreeMap<Rational, Vec<Term>> = BTreeMap::new();
+        for mut term in expanded(&collapsed) {
+            let power = term.variables.remove(variable).unwrap_or_else(Rational::zero);
+            by_power.entry(power).or_default().push(term);
+        }
+
+        let terms: Vec<Expr> = by_power.into_iter().rev().flat_map(|(power, terms)| collected(variable, power, merged(terms))).collect();
+        match terms.len() {
+            0 => create_number(0),
+            1 => terms.into_iter().next().unwrap(),
+            _ => SummationFunction::new(terms).into(),
+        }
+    }
+    ////// Collection //////
+
+
+    //---- Fractions ----//
+    // `expr` as its coefficient times powers of its other factors, variables included, as in 3x^2*(x + 1)^2
+    pub fn factored(expr: &Expr) -> (Rational, PowerList<Expr>) {
+        let term = term(expr);
+        let variables = term.variables.into_iter().map(|(variable, power)| (create_variable(variable.name(), 1), power));
+        (term.coefficient, variables.chain(term.factors).collect())
+    }
+
+    fn factor_term(coefficient: Rational, factors: PowerList<Expr>) -> Term {
+        factors.into_iter().fold(Term::constant(coefficient), |product, (factor, power)| {
+            let factor = term(&factor);
+            product.multiply(factor.pow(&power).unwrap_or_else(|| Term::factor(factor.to_expr(), power)))
+        })
+    }
+
+    // the product of `coefficient` and the powers of `factors`, in canonical form
+    pub fn factor_product(coefficient: Rational, factors: PowerList<Expr>) -> Expr {
+        factor_term(coefficient, factors).to_expr()
+    }
+
+    // the sum of such products, in canonical form
+    pub fn factor_sum(products: Vec<(Rational, PowerList<Expr>)>) -> Expr {
+        let terms = products.into_iter().map(|(coefficient, factors)| factor_term(coefficient, factors)).collect();
+        sum_expr(&merged(terms))
+    }
+
+    // The sum of `terms` over their least common denominator, kept as a product of powers of the
+    // denominators' factors. Each numerator is multiplied by what its own denominator lacks and
+    // expanded; cancelling and normalizing the coefficients is left to the quotient's collapse.
+    // There is no such sum when a denominator is zero, even one that only cancels to zero
+    pub fn together(terms: &[Expr]) -> Option<Expr> {
+        let parts: Vec<(&Expr, Term)> = terms
+            .iter()
+            .map(|expr| match expr {
+                Expr::Quotient(quotient) => (quotient.numerator(), term(quotient.denominator())),
+                _ => (expr, Term::constant(Rational::one())),
+            })
+            .collect();
+        if parts.iter().any(|(_, denominator)| denominator.coefficient.is_zero()) {
+            return None;
+        }
+
+        // the highest power of every factor that occurs
+        let mut common = Term::constant(Rational::one());
+        for (_, denominator) in &parts {
+            for (variable, power) in &denominator.variables {
+                if power.is_positive() && common.variables.get(variable).is_none_or(|existing| existing < power) {
+                    common.variables.insert(variable.clone(), power.clone());
+                }
+            }
+            for (factor, power) in &denominator.factors {
+                if power.is_positive() && common.factors.get(factor).is_none_or(|existing| existing < power) {
+                    common.factors.insert(factor.clone(), power.clone());
+                }
+            }
+        }
+
+        let mut numerator = vec![];
+        for (term, denominator) in parts {
+            let reciprocal = denominator.pow(&-Rational::one()).unwrap_or_else(|| Term::factor(denominator.to_expr(), -Rational::one()));
+            let missing = common.clone().multiply(reciprocal);
+            numerator.extend(distribute(&expanded(term), &expanded(&missing.to_expr())));
+        }
+
+        Some(DivisionFunction::new(sum_expr(&merged(numerator)), common.to_expr()).into())
+    }
+    ////// Fractions //////
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::parser::parser::parse;
+        use std::collections::HashMap;
+
+        fn canonical_str(input: &str) -> String {
+            canonicalize(&parse(input).unwrap().to_expr()).to_str()
+        }
+
+        #[test]
+        fn terms_sort_in_graded_lexicographic_order() {
+            assert_eq!(canonical_str("y + x^2 + 1 + x*y + x"), "x^2 + x*y + x + y + 1");
+            assert_eq!(canonical_str("3 + 2*x + x^3*y + y^2"), "x^3*y + y^2 + 2x + 3");
+            assert_eq!(canonical_str("z*y*x + x^2"), "x*y*z + x^2");
+            // other factors come after the variables, and the constant term stays last
+            assert_eq!(canonical_str("sin(x) + x + 1"), "x + sin(x) + 1");
+        }
+
+        #[test]
+        fn like_terms_and_factors_merge() {
+            assert_eq!(canonical_str("b*a + a*b"), "2a*b");
+            assert_eq!(canonical_str("x + 1 - x"), "1");
+            assert_eq!(canonical_str("2*(x + y) - 2*y"), "2x");
+            assert_eq!(canonical_str("x*x^2*y/y"), "x^3");
+        }
+
+        #[test]
+        fn equal_polynomials_print_the_same() {
+            assert_eq!(canonical_str("(y + 1)*x + x^2"), canonical_str("x^2 + x*(1 + y)"));
+            assert_eq!(canonical_str("2x*3y"), canonical_str("6*y*x"));
+        }
+
+        #[test]
+        fn powers_of_powers_keep_the_value_of_negative_bases() {
+            let env = |name: &str, value: f64| HashMap::from([(VariableIdentifier::new(name), value)]);
+            for (input, name) in [("(x^2)^(1/2)", "x"), ("((y - 1)^2)^(1/2)", "y"), ("(x^2)^(1/4)", "x"), ("(x^2*y^2)^(1/2)", "x")] {
+                let expr = parse(input).unwrap().to_expr();
+                let mut env = env(name, -0.7);
+                env.insert(VariableIdentifier::new("y"), 0.3);
+                let expected = expr.evaluate(&env).unwrap();
+                for result in [canonicalize(&expr), expand(&expr)] {
+                    assert!((result.evaluate(&env).unwrap() - expected).abs() < 1e-12, "{}: {}", input, result.to_str());
+                }
+            }
+
+            // odd powers and integer exponents still multiply out
+            assert_eq!(canonical_str("(x^3)^(1/2)"), "x^(3/2)");
+            assert_eq!(canonical_str("(x^(1/2))^4"), "x^2");
+            assert_eq!(canonical_str("(x^3)^(1/3)"), "(x^3)^(1/3)");
+        }
+
+        fn expanded_str(input: &str) -> String {
+            expand(&parse(input).unwrap().to_expr()).to_str()
+        }
+
+        #[test]
+        fn natural_powers_of_sums_take_binomial_coefficients() {
+            assert_eq!(expanded_str("(x + 1)^2"), "x^2 + 2x + 1");
+            assert_eq!(expanded_str("(x + 1)^5"), "x^5 + 5x^4 + 10x^3 + 10x^2 + 5x + 1");
+            assert_eq!(expanded_str("(x - y)^3"), "x^3 - 3x^2*y + 3x*y^2 - y^3");
+            assert_eq!(expanded_str("(a + b + c)^2"), "a^2 + 2a*b + 2a*c + b^2 + 2b*c + c^2");
+
+            // the coefficients of (x + 1)^n are the rows of Pascal's triangle
+            let x = VariableIdentifier::new("x");
+            let mut row = vec![Rational::one()];
+            for n in 1..=12 {
+                row = (0..=n).map(|k| if k == 0 || k == n { Rational::one() } else { &row[k - 1] + &row[k] }).collect();
+                let terms = expanded(&parse(&format!("(x + 1)^{}", n)).unwrap().to_expr());
+                let coefficients: Vec<Rational> = terms.iter().map(|term| term.coefficient.clone()).collect();
+                assert_eq!(coefficients, row);
+                assert!(terms.iter().enumerate().all(|(i, term)| term.variables.get(&x).cloned().unwrap_or_else(Rational::zero) == Rational::from((n - i) as i64)));
+            }
+        }
+
+        #[test]
+        fn products_distribute_over_sums() {
+            assert_eq!(expanded_str("(x + 1)*(x - 1)"), "x^2 - 1");
+            assert_eq!(expanded_str("2(x + 1)^2 - 2x^2"), "4x + 2");
+            assert_eq!(expanded_str("(x^2 + 1)(x + sin(x))"), "x^3 + x^2*sin(x) + x + sin(x)");
+        }
+
+        #[test]
+        fn denominators_and_other_powers_stay_unexpanded() {
+            assert_eq!(expanded_str("1/(x + 1)^2"), "1/((x + 1)^2)");
+            assert_eq!(expanded_str("(x + 1)^(1/2)"), "(x + 1)^(1/2)");
+        }
+
+        fn collected_str(input: &str) -> String {
+            collect(&parse(input).unwrap().to_expr(), &VariableIdentifier::new("x")).to_str()
+        }
+
+        #[test]
+        fn terms_collect_by_descending_powers() {
+            assert_eq!(collected_str("x*c + x^2*a + x^2*b + 1"), "(a + b)*x^2 + c*x + 1");
+            assert_eq!(collected_str("3*x^2*y + x^2 + 2"), "(3y + 1)*x^2 + 2");
+            assert_eq!(collected_str("(x + y)^2"), "x^2 + 2y*x + y^2");
+            assert_eq!(collected_str("a/x + b/x + 1"), "1 + (a + b)/x");
+        }
+
+        #[test]
+        fn coefficients_go_before_the_power() {
+            assert_eq!(collected_str("x*c + d"), "c*x + d");
+            assert_eq!(collected_str("x^2*c"), "c*x^2");
+            assert_eq!(collected_str("x*(a + b)"), "(a + b)*x");
+            assert_eq!(collected_str("x*sin(y)"), "sin(y)*x");
+            assert_eq!(collected_str("x^2*sin(y)*2"), "2*sin(y)*x^2");
+            assert_eq!(collected_str("3x^2 - x"), "3x^2 - x");
+        }
+    }
+}
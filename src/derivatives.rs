@@ -0,0 +1,150 @@
+pub mod derivatives {
+    use std::collections::HashMap;
+    use crate::canonical::canonical::canonicalize;
+    use crate::entity::entity::{DataState, Entity, Expr, VariableIdentifier};
+
+    // the derivative with respect to `var`, collapsed and in canonical form
+    fn partial(expr: &Expr, var: &VariableIdentifier) -> Expr {
+        let mut derivative = expr.differentiate_wrt(&DataState::new(var.clone()));
+        derivative.collapse();
+        canonicalize(&derivative)
+    }
+
+    //---- Higher Derivatives ----//
+    // Derivatives of every order computed so far, per expression and variable, so that asking for
+    // a higher order only differentiates past the last one known
+    #[derive(Debug, Clone, Default)]
+    pub struct DerivativeCache {
+        orders: HashMap<(Expr, VariableIdentifier), Vec<Expr>>,
+    }
+
+    impl DerivativeCache {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        // The derivatives of `expr` with respect to `var` of orders 0 to n. Each one is collapsed
+        // and brought to canonical form before it is differentiated again, so that products of
+        // functions do not nest deeper with every order
+        pub fn derivatives(&mut self, expr: &Expr, var: &VariableIdentifier, n: usize) -> &[Expr] {
+            let orders = self.orders.entry((expr.clone(), var.clone())).or_insert_with(|| vec![canonicalize(expr)]);
+
+            while orders.len() <= n {
+                orders.push(partial(orders.last().unwrap(), var));
+            }
+            &orders[..=n]
+        }
+
+        pub fn nth_derivative(&mut self, expr: &Expr, var: &VariableIdentifier, n: usize) -> Expr {
+            self.derivatives(expr, var, n)[n].clone()
+        }
+    }
+
+    // the facade over a cache, which the caller keeps so that later calls reuse what it holds
+    pub fn nth_derivative(cache: &mut DerivativeCache, expr: &dyn Entity, var: &VariableIdentifier, n: usize) -> Box<dyn Entity> {
+        Box::new(cache.nth_derivative(&expr.to_expr(), var, n))
+    }
+
+    // every derivative of `expr` from order 0, the expression itself, up to order n
+    pub fn derivatives(cache: &mut DerivativeCache, expr: &dyn Entity, var: &VariableIdentifier, n: usize) -> Vec<Box<dyn Entity>> {
+        cache.derivatives(&expr.to_expr(), var, n).iter().map(|derivative| Box::new(derivative.clone()) as Box<dyn Entity>).collect()
+    }
+    ////// Higher Derivatives //////
+
+
+    //---- Partial Derivatives ----//
+    // the first partial derivatives of `expr`, one per variable in `vars`
+    pub fn gradient(expr: &dyn Entity, vars: &[VariableIdentifier]) -> Vec<Box<dyn Entity>> {
+        let expr = canonicalize(&expr.to_expr());
+        vars.iter().map(|var| Box::new(partial(&expr, var)) as Box<dyn Entity>).collect()
+    }
+
+    // The second partial derivatives of `expr`, where row i, column j is d/d(vars[j]) of d/d(vars[i]).
+    // Mixed partials of these expressions agree, so each pair is only differentiated once
+    pub fn hessian(expr: &dyn Entity, vars: &[VariableIdentifier]) -> Vec<Vec<Box<dyn Entity>>> {
+        let expr = canonicalize(&expr.to_expr());
+        let first: Vec<Expr> = vars.iter().map(|var| partial(&expr, var)).collect();
+
+        let mut second: Vec<Vec<Expr>> = vec![];
+        for i in 0..vars.len() {
+            let row = (0..vars.len()).map(|j| if j < i { second[j][i].clone() } else { partial(&first[i], &vars[j]) }).collect();
+            second.push(row);
+        }
+
+        second
+            .into_iter()
+            .map(|row| row.into_iter().map(|derivative| Box::new(derivative) as Box<dyn Entity>).collect())
+            .collect()
+    }
+    ////// Partial Derivatives //////
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::parser::parser::parse;
+
+        #[test]
+        fn derivatives_stay_small() {
+            let x = VariableIdentifier::new("x");
+            let expr = parse("ln(x)/x").unwrap().to_expr();
+            let mut cache = DerivativeCache::new();
+
+            // the nth derivative is (-1)^n n! (ln(x) - H_n)/x^(n + 1)
+            let (mut factorial, mut harmonic) = (1.0, 0.0);
+            for (n, derivative) in cache.derivatives(&expr, &x, 8).iter().enumerate().skip(1) {
+                factorial *= n as f64;
+                harmonic += 1.0 / n as f64;
+                let sign = if n % 2 == 0 { 1.0 } else { -1.0 };
+                let expected = sign * factorial * (2f64.ln() - harmonic) / 2f64.powi(n as i32 + 1);
+
+                let value = derivative.evaluate(&HashMap::from([(x.clone(), 2.0)])).unwrap();
+                assert!((value - expected).abs() < 1e-9 * expected.abs(), "order {}: {}", n, derivative.to_str());
+                assert!(derivative.to_str().len() < 32, "order {}: {}", n, derivative.to_str());
+            }
+        }
+
+        #[test]
+        fn denominators_stay_factored() {
+            let x = VariableIdentifier::new("x");
+            let expr = parse("1/(x + 1)").unwrap();
+            let mut cache = DerivativeCache::new();
+            assert_eq!(nth_derivative(&mut cache, expr.as_ref(), &x, 10).to_str(), "3628800/((x + 1)^11)");
+
+            // the lower orders were kept on the way
+            let lower = derivatives(&mut cache, expr.as_ref(), &x, 2);
+            assert_eq!(lower.iter().map(|derivative| derivative.to_str()).collect::<Vec<_>>(), ["1/(x + 1)", "-1/((x + 1)^2)", "2/((x + 1)^3)"]);
+        }
+
+        #[test]
+        fn hessian_matches_the_partials_in_both_triangles() {
+            let vars = [VariableIdentifier::new("x"), VariableIdentifier::new("y")];
+            let expr = parse("x^2*y^3 + sin(x*y)").unwrap();
+            let (x, y): (f64, f64) = (0.7, -1.3);
+            let env = HashMap::from([(vars[0].clone(), x), (vars[1].clone(), y)]);
+
+            let gradient = gradient(expr.as_ref(), &vars);
+            let expected = [2.0 * x * y.powi(3) + y * (x * y).cos(), 3.0 * x * x * y * y + x * (x * y).cos()];
+            for (derivative, expected) in gradient.iter().zip(expected) {
+                assert!((derivative.evaluate(&env).unwrap() - expected).abs() < 1e-9, "{}", derivative.to_str());
+            }
+
+            let hessian = hessian(expr.as_ref(), &vars);
+            let mixed = 6.0 * x * y * y + (x * y).cos() - x * y * (x * y).sin();
+            let expected = [
+                [2.0 * y.powi(3) - y * y * (x * y).sin(), mixed],
+                [mixed, 6.0 * x * x * y - x * x * (x * y).sin()],
+            ];
+            let canonical = canonicalize(&expr.to_expr());
+            for i in 0..vars.len() {
+                for j in 0..vars.len() {
+                    // each entry against its own derivative, not the one it may have been copied from
+                    let direct = partial(&partial(&canonical, &vars[i]), &vars[j]).evaluate(&env).unwrap();
+                    let value = hessian[i][j].evaluate(&env).unwrap();
+                    assert!((value - direct).abs() < 1e-9, "({}, {}): {}", i, j, hessian[i][j].to_str());
+                    assert!((value - expected[i][j]).abs() < 1e-9, "({}, {}): {}", i, j, hessian[i][j].to_str());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,803 @@
+pub mod elementary {
+    use std::collections::HashMap;
+    use crate::rational::rational::Rational;
+    use crate::entity::entity::{as_number, create_number, CompositionFunction, DataState, DivisionFunction, EvalError, Expr, ExprNode, MultiplicationFunction, SummationFunction, UnaryFunction, VariableIdentifier};
+
+    //---- Functions ----//
+    // Every outer function a CompositionFunction can apply
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum ElementaryFunction {
+        Exp(ExpFunction),
+        Ln(LnFunction),
+        Log(LogFunction),
+        Sqrt(SqrtFunction),
+        Trigonometric(TrigonometricFunction),
+        Hyperbolic(HyperbolicFunction),
+    }
+
+    impl ElementaryFunction {
+        fn function(&self) -> &dyn UnaryFunction {
+            match self {
+                ElementaryFunction::Exp(function) => function,
+                ElementaryFunction::Ln(function) => function,
+                ElementaryFunction::Log(function) => function,
+                ElementaryFunction::Sqrt(function) => function,
+                ElementaryFunction::Trigonometric(function) => function,
+                ElementaryFunction::Hyperbolic(function) => function,
+            }
+        }
+    }
+
+    impl UnaryFunction for ElementaryFunction {
+        fn name(&self) -> String {
+            self.function().name()
+        }
+
+        fn derivative(&self, inner: &Expr) -> Expr {
+            self.function().derivative(inner)
+        }
+
+        fn apply(&self, value: f64) -> f64 {
+            self.function().apply(value)
+        }
+
+        fn simplify(&self, inner: &Expr) -> Option<Expr> {
+            self.function().simplify(inner)
+        }
+    }
+    ////// Functions //////
+
+
+    //---- Constants ----//
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum NamedConstant {
+        Pi,
+    }
+
+    impl ExprNode for NamedConstant {
+        fn to_str(&self) -> String {
+            match self {
+                NamedConstant::Pi => "pi",
+            }.to_string()
+        }
+
+        fn differentiate(&self) -> Expr {
+            create_number(0)
+        }
+
+        fn differentiate_wrt(&self, _state: &DataState) -> Expr {
+            create_number(0)
+        }
+
+        fn evaluate(&self, _env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            match self {
+                NamedConstant::Pi => Ok(std::f64::consts::PI),
+            }
+        }
+
+        fn substitute(&self, _var: &VariableIdentifier, _replacement: &Expr) -> Expr {
+            Expr::Named(*self)
+        }
+
+        fn collapse(&mut self) {
+
+        }
+    }
+    ////// Constants //////
+
+
+    //---- Exponential ----//
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct ExpFunction;
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct LnFunction;
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct LogFunction {
+        base: Rational,
+    }
+
+    impl LogFunction {
+        pub fn new(base: Rational) -> Self {
+            Self { base }
+        }
+
+        pub fn base(&self) -> Rational {
+            self.base.clone()
+        }
+    }
+
+    impl UnaryFunction for ExpFunction {
+        fn name(&self) -> String {
+            "exp".to_string()
+        }
+
+        fn derivative(&self, inner: &Expr) -> Expr {
+            create_exp(inner.clone())
+        }
+
+        fn apply(&self, value: f64) -> f64 {
+            value.exp()
+        }
+
+        fn simplify(&self, inner: &Expr) -> Option<Expr> {
+            if inner.to_str() == "0" {
+                return Some(create_number(1));
+            }
+
+            applied_inner(inner, &ElementaryFunction::Ln(LnFunction)).cloned()
+        }
+    }
+
+    impl UnaryFunction for LnFunction {
+        fn name(&self) -> String {
+            "ln".to_string()
+        }
+
+        fn derivative(&self, inner: &Expr) -> Expr {
+            DivisionFunction::new(create_number(1), inner.clone()).into()
+        }
+
+        fn apply(&self, value: f64) -> f64 {
+            value.ln()
+        }
+
+        fn simplify(&self, inner: &Expr) -> Option<Expr> {
+            if inner.to_str() == "1" {
+                return Some(create_number(0));
+            }
+
+            applied_inner(inner, &ElementaryFunction::Exp(ExpFunction)).cloned()
+        }
+    }
+
+    impl UnaryFunction for LogFunction {
+        fn name(&self) -> String {
+            format!("log_{}", self.base)
+        }
+
+        fn derivative(&self, inner: &Expr) -> Expr {
+            // 1 / (u ln b)
+            let denominator = MultiplicationFunction::new(inner.clone(), create_ln(create_number(self.base.clone())));
+            DivisionFunction::new(create_number(1), denominator.into()).into()
+        }
+
+        fn apply(&self, value: f64) -> f64 {
+            value.log(self.base.to_f64())
+        }
+
+        fn simplify(&self, inner: &Expr) -> Option<Expr> {
+            let s = inner.to_str();
+            if s == "1" {
+                Some(create_number(0))
+            }
+            else if s == self.base.to_string() {
+                Some(create_number(1))
+            }
+            else {
+                None
+            }
+        }
+    }
+    ////// Exponential //////
+
+
+    //---- Roots ----//
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct SqrtFunction;
+
+    impl UnaryFunction for SqrtFunction {
+        fn name(&self) -> String {
+            "sqrt".to_string()
+        }
+
+        fn derivative(&self, inner: &Expr) -> Expr {
+            // 1 / (2 sqrt(u))
+            let denominator = MultiplicationFunction::new(create_number(2), create_sqrt(inner.clone()));
+            DivisionFunction::new(create_number(1), denominator.into()).into()
+        }
+
+        fn apply(&self, value: f64) -> f64 {
+            value.sqrt()
+        }
+
+        fn simplify(&self, inner: &Expr) -> Option<Expr> {
+            as_number(inner)?.sqrt().map(create_number)
+        }
+    }
+    ////// Roots //////
+
+
+    //---- Trigonometric ----//
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum Trigonometric {
+        Sin,
+        Cos,
+        Tan,
+        Sec,
+        Csc,
+        Cot,
+        Asin,
+        Acos,
+        Atan,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct TrigonometricFunction {
+        kind: Trigonometric,
+    }
+
+    impl TrigonometricFunction {
+        pub fn new(kind: Trigonometric) -> Self {
+            Self { kind }
+        }
+
+        pub fn kind(&self) -> Trigonometric {
+            self.kind
+        }
+    }
+
+    impl UnaryFunction for TrigonometricFunction {
+        fn name(&self) -> String {
+            match self.kind {
+                Trigonometric::Sin => "sin",
+                Trigonometric::Cos => "cos",
+                Trigonometric::Tan => "tan",
+                Trigonometric::Sec => "sec",
+                Trigonometric::Csc => "csc",
+                Trigonometric::Cot => "cot",
+                Trigonometric::Asin => "asin",
+                Trigonometric::Acos => "acos",
+                Trigonometric::Atan => "atan",
+            }.to_string()
+        }
+
+        fn derivative(&self, inner: &Expr) -> Expr {
+            let u = || inner.clone();
+            match self.kind {
+                Trigonometric::Sin => create_trig(Trigonometric::Cos, u()),
+                Trigonometric::Cos => negate(create_trig(Trigonometric::Sin, u())),
+                Trigonometric::Tan => square(create_trig(Trigonometric::Sec, u())),
+                Trigonometric::Sec => MultiplicationFunction::new(create_trig(Trigonometric::Sec, u()), create_trig(Trigonometric::Tan, u())).into(),
+                Trigonometric::Csc => negate(MultiplicationFunction::new(create_trig(Trigonometric::Csc, u()), create_trig(Trigonometric::Cot, u())).into()),
+                Trigonometric::Cot => negate(square(create_trig(Trigonometric::Csc, u()))),
+                Trigonometric::Asin | Trigonometric::Acos => {
+                    // +-1 / sqrt(1 - u^2)
+                    let root = create_sqrt(SummationFunction::new(vec![create_number(1), negate(square(u()))]).into());
+                    let sign = if self.kind == Trigonometric::Asin { 1 } else { -1 };
+                    DivisionFunction::new(create_number(sign), root).into()
+                },
+                Trigonometric::Atan => {
+                    let denominator = SummationFunction::new(vec![create_number(1), square(u())]);
+                    DivisionFunction::new(create_number(1), denominator.into()).into()
+                },
+            }
+        }
+
+        fn apply(&self, value: f64) -> f64 {
+            match self.kind {
+                Trigonometric::Sin => value.sin(),
+                Trigonometric::Cos => value.cos(),
+                Trigonometric::Tan => value.tan(),
+                Trigonometric::Sec => 1.0 / value.cos(),
+                Trigonometric::Csc => 1.0 / value.sin(),
+                Trigonometric::Cot => 1.0 / value.tan(),
+                Trigonometric::Asin => value.asin(),
+                Trigonometric::Acos => value.acos(),
+                Trigonometric::Atan => value.atan(),
+            }
+        }
+
+        fn simplify(&self, inner: &Expr) -> Option<Expr> {
+            match self.kind {
+                Trigonometric::Asin | Trigonometric::Acos | Trigonometric::Atan => inverse_exact_value(self.kind, as_number(inner)?),
+                _ => exact_value(self.kind, pi_twelfths(inner)?).map(|value| value.to_expr()),
+            }
+        }
+    }
+
+    // numerator/denominator * sqrt(radicand), the form every exact value below takes
+    #[derive(Clone, Copy)]
+    struct Surd {
+        numerator: i64,
+        denominator: i64,
+        radicand: i64,
+    }
+
+    impl Surd {
+        fn new(numerator: i64, denominator: i64, radicand: i64) -> Self {
+            let mut numerator = numerator;
+            let mut radicand = if numerator == 0 { 1 } else { radicand };
+
+            // pull square factors out of the root
+            let mut factor = 2;
+            while factor * factor <= radicand {
+                while radicand % (factor * factor) == 0 {
+                    radicand /= factor * factor;
+                    numerator *= factor;
+                }
+                factor += 1;
+            }
+
+            let divisor = gcd(numerator, denominator) * denominator.signum();
+            Self { numerator: numerator / divisor, denominator: denominator / divisor, radicand }
+        }
+
+        fn divide(&self, other: &Surd) -> Option<Surd> {
+            if other.numerator == 0 {
+                return None;
+            }
+
+            // a sqrt(r) / (b sqrt(s)) = a sqrt(rs) / (bs)
+            Some(Surd::new(
+                self.numerator * other.denominator,
+                self.denominator * other.numerator * other.radicand,
+                self.radicand * other.radicand,
+            ))
+        }
+
+        fn coefficient(&self) -> Rational {
+            Rational::new(self.numerator, self.denominator)
+        }
+
+        fn to_expr(self) -> Expr {
+            let coefficient = create_number(self.coefficient());
+            if self.radicand == 1 {
+                return coefficient;
+            }
+
+            let root = create_sqrt(create_number(self.radicand));
+            if self.numerator == 1 && self.denominator == 1 {
+                return root;
+            }
+            MultiplicationFunction::new(coefficient, root).into()
+        }
+    }
+
+    // sin(n pi/12), known whenever n pi/12 is a multiple of pi/6 or pi/4
+    fn exact_sin(twelfths: i64) -> Option<Surd> {
+        let n = twelfths.rem_euclid(24);
+        let (n, sign) = if n > 12 { (n - 12, -1) } else { (n, 1) };
+        let n = if n > 6 { 12 - n } else { n };
+
+        let (numerator, denominator, radicand) = match n {
+            0 => (0, 1, 1),
+            2 => (1, 2, 1),
+            3 => (1, 2, 2),
+            4 => (1, 2, 3),
+            6 => (1, 1, 1),
+            _ => return None,
+        };
+        Some(Surd::new(sign * numerator, denominator, radicand))
+    }
+
+    fn exact_value(kind: Trigonometric, twelfths: i64) -> Option<Surd> {
+        let one = Surd::new(1, 1, 1);
+        let sin = exact_sin(twelfths)?;
+        let cos = exact_sin(twelfths + 6)?;
+
+        match kind {
+            Trigonometric::Sin => Some(sin),
+            Trigonometric::Cos => Some(cos),
+            Trigonometric::Tan => sin.divide(&cos),
+            Trigonometric::Sec => one.divide(&cos),
+            Trigonometric::Csc => one.divide(&sin),
+            Trigonometric::Cot => cos.divide(&sin),
+            _ => None,
+        }
+    }
+
+    // The inverse functions only undo the rational entries of the table, over their principal ranges
+    fn inverse_exact_value(kind: Trigonometric, value: Rational) -> Option<Expr> {
+        let (range, function) = match kind {
+            Trigonometric::Asin => (-6..=6, Trigonometric::Sin),
+            Trigonometric::Acos => (0..=12, Trigonometric::Cos),
+            Trigonometric::Atan => (-5..=5, Trigonometric::Tan),
+            _ => return None,
+        };
+
+        range
+            .into_iter()
+            .find(|&n| exact_value(function, n).is_some_and(|exact| exact.radicand == 1 && exact.coefficient() == value))
+            .map(create_pi_multiple)
+    }
+
+    // k when `entity` is k*pi for a plain number k
+    fn pi_multiple(entity: &Expr) -> Option<Rational> {
+        match entity {
+            Expr::Named(NamedConstant::Pi) => Some(Rational::one()),
+            Expr::Constant(_) => as_number(entity).filter(|value| value.is_zero()),
+            Expr::Product(product) => match (as_number(product.first()), as_number(product.second())) {
+                (Some(k), _) => Some(k * pi_multiple(product.second())?),
+                (_, Some(k)) => Some(k * pi_multiple(product.first())?),
+                _ => None,
+            },
+            Expr::Quotient(quotient) => {
+                let denominator = as_number(quotient.denominator()).filter(|d| !d.is_zero())?;
+                Some(pi_multiple(quotient.numerator())? / denominator)
+            },
+            _ => None,
+        }
+    }
+
+    fn pi_twelfths(entity: &Expr) -> Option<i64> {
+        let twelfths = pi_multiple(entity)? * Rational::from(12);
+        twelfths.to_i64()
+    }
+    ////// Trigonometric //////
+
+
+    //---- Hyperbolic ----//
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum Hyperbolic {
+        Sinh,
+        Cosh,
+        Tanh,
+        Asinh,
+        Acosh,
+        Atanh,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct HyperbolicFunction {
+        kind: Hyperbolic,
+    }
+
+    impl HyperbolicFunction {
+        pub fn new(kind: Hyperbolic) -> Self {
+            Self { kind }
+        }
+
+        pub fn kind(&self) -> Hyperbolic {
+            self.kind
+        }
+
+        // The function undone by this one, for the functions that have a two-sided inverse
+        fn inverse(&self) -> Option<Hyperbolic> {
+            match self.kind {
+                Hyperbolic::Sinh => Some(Hyperbolic::Asinh),
+                Hyperbolic::Cosh => Some(Hyperbolic::Acosh),
+                Hyperbolic::Tanh => Some(Hyperbolic::Atanh),
+                _ => None,
+            }
+        }
+    }
+
+    impl UnaryFunction for HyperbolicFunction {
+        fn name(&self) -> String {
+            match self.kind {
+                Hyperbolic::Sinh => "sinh",
+                Hyperbolic::Cosh => "cosh",
+                Hyperbolic::Tanh => "tanh",
+                Hyperbolic::Asinh => "asinh",
+                Hyperbolic::Acosh => "acosh",
+                Hyperbolic::Atanh => "atanh",
+            }.to_string()
+        }
+
+        fn derivative(&self, inner: &Expr) -> Expr {
+            let u = || inner.clone();
+            match self.kind {
+                Hyperbolic::Sinh => create_hyperbolic(Hyperbolic::Cosh, u()),
+                Hyperbolic::Cosh => create_hyperbolic(Hyperbolic::Sinh, u()),
+                Hyperbolic::Tanh => DivisionFunction::new(create_number(1), square(create_hyperbolic(Hyperbolic::Cosh, u()))).into(),
+                Hyperbolic::Asinh | Hyperbolic::Acosh => {
+                    // 1 / sqrt(u^2 +- 1)
+                    let one = if self.kind == Hyperbolic::Asinh { 1 } else { -1 };
+                    let root = create_sqrt(SummationFunction::new(vec![square(u()), create_number(one)]).into());
+                    DivisionFunction::new(create_number(1), root).into()
+                },
+                Hyperbolic::Atanh => {
+                    let denominator = SummationFunction::new(vec![create_number(1), negate(square(u()))]);
+                    DivisionFunction::new(create_number(1), denominator.into()).into()
+                },
+            }
+        }
+
+        fn apply(&self, value: f64) -> f64 {
+            match self.kind {
+                Hyperbolic::Sinh => value.sinh(),
+                Hyperbolic::Cosh => value.cosh(),
+                Hyperbolic::Tanh => value.tanh(),
+                Hyperbolic::Asinh => value.asinh(),
+                Hyperbolic::Acosh => value.acosh(),
+                Hyperbolic::Atanh => value.atanh(),
+            }
+        }
+
+        fn simplify(&self, inner: &Expr) -> Option<Expr> {
+            // f(f^-1(u)) = u, but not the other way around since cosh is not injective
+            if let Some(inverse) = self.inverse() {
+                if let Some(applied) = applied_inner(inner, &ElementaryFunction::Hyperbolic(HyperbolicFunction::new(inverse))) {
+                    return Some(applied.clone());
+                }
+            }
+
+            let value = as_number(inner)?;
+            let zero_at = if self.kind == Hyperbolic::Acosh { Rational::one() } else { Rational::zero() };
+            if value != zero_at {
+                return None;
+            }
+            Some(create_number(if self.kind == Hyperbolic::Cosh { 1 } else { 0 }))
+        }
+    }
+
+    // k and the applied function when `term` is k*f(u)*f(u) or k*f(u)^2
+    fn squared_term(term: &Expr) -> Option<(Rational, &CompositionFunction)> {
+        match term {
+            Expr::Power(power) => {
+                let Expr::Composition(function) = power.base() else {
+                    return None;
+                };
+                (as_number(power.exponent()) == Some(Rational::from(2))).then_some((Rational::one(), function))
+            },
+            Expr::Product(product) => {
+                if let Some(k) = as_number(product.first()) {
+                    return squared_term(product.second()).map(|(c, f)| (k * c, f));
+                }
+                if let Some(k) = as_number(product.second()) {
+                    return squared_term(product.first()).map(|(c, f)| (k * c, f));
+                }
+
+                let Expr::Composition(function) = product.first() else {
+                    return None;
+                };
+                if product.second() != product.first() {
+                    return None;
+                }
+                Some((Rational::one(), function))
+            },
+            _ => None,
+        }
+    }
+
+    fn hyperbolic_square(term: &Expr) -> Option<(Rational, Hyperbolic, &Expr)> {
+        let (k, function) = squared_term(term)?;
+        let ElementaryFunction::Hyperbolic(hyperbolic) = function.outer() else {
+            return None;
+        };
+        Some((k, hyperbolic.kind, function.inner()))
+    }
+
+    // Folds one pair of sum terms k cosh(u)^2 - k sinh(u)^2 into k, returning whether it did
+    pub fn fold_identities(terms: &mut Vec<Expr>) -> bool {
+        for i in 0..terms.len() {
+            let Some((k, Hyperbolic::Cosh, inner)) = hyperbolic_square(&terms[i]) else {
+                continue;
+            };
+
+            let partner = (0..terms.len()).find(|&j| {
+                hyperbolic_square(&terms[j]).is_some_and(|(c, kind, u)| kind == Hyperbolic::Sinh && c == -&k && u == inner)
+            });
+            if let Some(j) = partner {
+                terms[i] = create_number(k);
+                terms.remove(j);
+                return true;
+            }
+        }
+        false
+    }
+    ////// Hyperbolic //////
+
+
+    //---- Helper Methods ----//
+    // u when `entity` is outer(u)
+    fn applied_inner<'a>(entity: &'a Expr, outer: &ElementaryFunction) -> Option<&'a Expr> {
+        match entity {
+            Expr::Composition(composition) if composition.outer() == outer => Some(composition.inner()),
+            _ => None,
+        }
+    }
+
+    pub fn create_exp(inner: Expr) -> Expr {
+        CompositionFunction::new(ElementaryFunction::Exp(ExpFunction), inner).into()
+    }
+
+    pub fn create_ln(inner: Expr) -> Expr {
+        CompositionFunction::new(ElementaryFunction::Ln(LnFunction), inner).into()
+    }
+
+    pub fn create_log(base: Rational, inner: Expr) -> Expr {
+        CompositionFunction::new(ElementaryFunction::Log(LogFunction::new(base)), inner).into()
+    }
+
+    pub fn create_sqrt(inner: Expr) -> Expr {
+        CompositionFunction::new(ElementaryFunction::Sqrt(SqrtFunction), inner).into()
+    }
+
+    pub fn create_trig(kind: Trigonometric, inner: Expr) -> Expr {
+        CompositionFunction::new(ElementaryFunction::Trigonometric(TrigonometricFunction::new(kind)), inner).into()
+    }
+
+    pub fn create_hyperbolic(kind: Hyperbolic, inner: Expr) -> Expr {
+        CompositionFunction::new(ElementaryFunction::Hyperbolic(HyperbolicFunction::new(kind)), inner).into()
+    }
+
+    // n pi/12 in lowest terms
+    fn create_pi_multiple(twelfths: i64) -> Expr {
+        let divisor = gcd(twelfths, 12);
+        let (numerator, denominator) = (twelfths / divisor, 12 / divisor);
+        if numerator == 0 {
+            return create_number(0);
+        }
+
+        let multiple: Expr = if numerator == 1 {
+            NamedConstant::Pi.into()
+        }
+        else {
+            MultiplicationFunction::new(create_number(numerator), NamedConstant::Pi.into()).into()
+        };
+
+        if denominator == 1 {
+            return multiple;
+        }
+        DivisionFunction::new(multiple, create_number(denominator)).into()
+    }
+
+    fn negate(entity: Expr) -> Expr {
+        MultiplicationFunction::new(create_number(-1), entity).into()
+    }
+
+    fn square(entity: Expr) -> Expr {
+        MultiplicationFunction::new(entity.clone(), entity).into()
+    }
+
+    fn gcd(a: i64, b: i64) -> i64 {
+        let (mut a, mut b) = (a.abs(), b.abs());
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    }
+
+    // Looks up a named constant, e.g. `pi`
+    pub fn create_constant(name: &str) -> Option<Expr> {
+        match name {
+            "pi" => Some(NamedConstant::Pi.into()),
+            _ => None,
+        }
+    }
+
+    // Looks up a function by the name it is written with, e.g. `ln` or `log_2`
+    pub fn create_function(name: &str) -> Option<ElementaryFunction> {
+        match name {
+            "exp" => Some(ElementaryFunction::Exp(ExpFunction)),
+            "ln" => Some(ElementaryFunction::Ln(LnFunction)),
+            "log" => Some(ElementaryFunction::Log(LogFunction::new(Rational::from(10)))),
+            "sqrt" => Some(ElementaryFunction::Sqrt(SqrtFunction)),
+            "sin" => Some(ElementaryFunction::Trigonometric(TrigonometricFunction::new(Trigonometric::Sin))),
+            "cos" => Some(ElementaryFunction::Trigonometric(TrigonometricFunction::new(Trigonometric::Cos))),
+            "tan" => Some(ElementaryFunction::Trigonometric(TrigonometricFunction::new(Trigonometric::Tan))),
+            "sec" => Some(ElementaryFunction::Trigonometric(TrigonometricFunction::new(Trigonometric::Sec))),
+            "csc" => Some(ElementaryFunction::Trigonometric(TrigonometricFunction::new(Trigonometric::Csc))),
+            "cot" => Some(ElementaryFunction::Trigonometric(TrigonometricFunction::new(Trigonometric::Cot))),
+            "asin" => Some(ElementaryFunction::Trigonometric(TrigonometricFunction::new(Trigonometric::Asin))),
+            "acos" => Some(ElementaryFunction::Trigonometric(TrigonometricFunction::new(Trigonometric::Acos))),
+            "atan" => Some(ElementaryFunction::Trigonometric(TrigonometricFunction::new(Trigonometric::Atan))),
+            "sinh" => Some(ElementaryFunction::Hyperbolic(HyperbolicFunction::new(Hyperbolic::Sinh))),
+            "cosh" => Some(ElementaryFunction::Hyperbolic(HyperbolicFunction::new(Hyperbolic::Cosh))),
+            "tanh" => Some(ElementaryFunction::Hyperbolic(HyperbolicFunction::new(Hyperbolic::Tanh))),
+            "asinh" => Some(ElementaryFunction::Hyperbolic(HyperbolicFunction::new(Hyperbolic::Asinh))),
+            "acosh" => Some(ElementaryFunction::Hyperbolic(HyperbolicFunction::new(Hyperbolic::Acosh))),
+            "atanh" => Some(ElementaryFunction::Hyperbolic(HyperbolicFunction::new(Hyperbolic::Atanh))),
+            _ => {
+                let base: Rational = name.strip_prefix("log_")?.parse().ok()?;
+                if !base.is_positive() || base == Rational::one() {
+                    return None;
+                }
+                Some(ElementaryFunction::Log(LogFunction::new(base)))
+            },
+        }
+    }
+    ////// Helper Methods //////
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::parser::parser::parse;
+
+        fn collapsed(input: &str) -> Expr {
+            let mut expr = parse(input).unwrap().to_expr();
+            expr.collapse();
+            expr
+        }
+
+        fn value(input: &str, x: f64) -> Result<f64, EvalError> {
+            collapsed(input).evaluate(&HashMap::from([(VariableIdentifier::new("x"), x)]))
+        }
+
+        fn domain_error(function: &str, argument: f64) -> Result<f64, EvalError> {
+            Err(EvalError::Domain { function: function.to_string(), argument })
+        }
+
+        #[test]
+        fn exponentials_and_logarithms_simplify() {
+            assert_eq!(collapsed("exp(0)").to_str(), "1");
+            assert_eq!(collapsed("ln(1)").to_str(), "0");
+            assert_eq!(collapsed("exp(ln(x))").to_str(), "x");
+            assert_eq!(collapsed("ln(exp(x))").to_str(), "x");
+            assert_eq!(collapsed("log_2(1)").to_str(), "0");
+            assert_eq!(collapsed("log_2(2)").to_str(), "1");
+            assert_eq!(collapsed("log(x)").to_str(), "log_10(x)");
+        }
+
+        #[test]
+        fn log_b_differentiates_through_ln_b() {
+            let derivative = collapsed("log_2(x^2)").differentiate_wrt(&DataState::new(VariableIdentifier::new("x")));
+            let env = HashMap::from([(VariableIdentifier::new("x"), 3.0)]);
+            assert!((derivative.evaluate(&env).unwrap() - 2.0 / (3.0 * 2f64.ln())).abs() < 1e-12);
+            assert_eq!(value("log_2(x)", 8.0), Ok(3.0));
+
+            // bases that are not positive or are 1 name no function
+            assert!(parse("log_1(x)").is_err());
+            assert!(parse("log_0(x)").is_err());
+        }
+
+        #[test]
+        fn logarithms_report_their_domain() {
+            assert_eq!(value("ln(x)", -1.0), domain_error("ln", -1.0));
+            assert_eq!(value("ln(x - 2)", 2.0), domain_error("ln", 0.0));
+            assert_eq!(value("log_2(x)", -8.0), domain_error("log_2", -8.0));
+        }
+
+        #[test]
+        fn trigonometric_functions_have_exact_values_at_multiples_of_pi() {
+            assert_eq!(collapsed("sin(pi/6)").to_str(), "1/2");
+            assert_eq!(collapsed("cos(pi/4)").to_str(), "1/2*sqrt(2)");
+            assert_eq!(collapsed("tan(pi/3)").to_str(), "sqrt(3)");
+            assert_eq!(collapsed("sin(5*pi/4)").to_str(), "-1/2*sqrt(2)");
+            assert_eq!(collapsed("cos(pi)").to_str(), "-1");
+            assert_eq!(collapsed("sec(pi/3)").to_str(), "2");
+            assert_eq!(collapsed("cot(pi/4)").to_str(), "1");
+
+            // no value at a pole, nor outside of the table
+            assert_eq!(collapsed("tan(pi/2)").to_str(), "tan(1/2*pi)");
+            assert_eq!(collapsed("sin(pi/5)").to_str(), "sin(1/5*pi)");
+        }
+
+        #[test]
+        fn inverse_functions_undo_rational_values() {
+            assert_eq!(collapsed("asin(1/2)").to_str(), "pi/6");
+            assert_eq!(collapsed("acos(-1/2)").to_str(), "2*pi/3");
+            assert_eq!(collapsed("atan(1)").to_str(), "pi/4");
+            assert_eq!(collapsed("asin(1/3)").to_str(), "asin(1/3)");
+        }
+
+        #[test]
+        fn inverse_trigonometric_functions_report_their_domain() {
+            assert_eq!(value("asin(x)", 2.0), domain_error("asin", 2.0));
+            assert_eq!(value("acos(x)", -1.5), domain_error("acos", -1.5));
+            assert_eq!(value("sqrt(x)", -1.0), domain_error("sqrt", -1.0));
+        }
+
+        #[test]
+        fn cosh_squared_minus_sinh_squared_folds() {
+            assert_eq!(collapsed("cosh(x)^2 - sinh(x)^2").to_str(), "1");
+            assert_eq!(collapsed("cosh(x)*cosh(x) - sinh(x)*sinh(x)").to_str(), "1");
+            assert_eq!(collapsed("sinh(x)^2 - cosh(x)^2").to_str(), "-1");
+            assert_eq!(collapsed("3*cosh(x)^2 - 3*sinh(x)^2 + x").to_str(), "3 + x");
+
+            // only the squares of the same argument with opposite coefficients
+            assert_eq!(collapsed("cosh(2x)^2 - sinh(x)^2").to_str(), "cosh(2x)^2 - sinh(x)^2");
+            assert_eq!(collapsed("2*cosh(x)^2 - sinh(x)^2").to_str(), "2*cosh(x)^2 - sinh(x)^2");
+        }
+
+        #[test]
+        fn hyperbolic_functions_simplify() {
+            assert_eq!(collapsed("cosh(0)").to_str(), "1");
+            assert_eq!(collapsed("sinh(0)").to_str(), "0");
+            assert_eq!(collapsed("acosh(1)").to_str(), "0");
+            assert_eq!(collapsed("sinh(asinh(x))").to_str(), "x");
+            // cosh is not injective, so acosh does not undo it
+            assert_eq!(collapsed("acosh(cosh(x))").to_str(), "acosh(cosh(x))");
+        }
+
+        #[test]
+        fn inverse_hyperbolic_functions_report_their_domain() {
+            assert_eq!(value("acosh(x)", 0.5), domain_error("acosh", 0.5));
+            assert_eq!(value("atanh(x)", 1.0), domain_error("atanh", 1.0));
+        }
+    }
+}
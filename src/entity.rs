@@ -1,6 +1,10 @@
 pub mod entity {
-    use std::{any::Any, borrow::Borrow, collections::HashMap, marker::PhantomData};
+    use std::{collections::HashMap, fmt, rc::Rc};
     use dyn_clone::DynClone;
+    use crate::canonical::canonical::together;
+    use crate::elementary::elementary::{create_ln, fold_identities, ElementaryFunction, NamedConstant};
+    use crate::polynomial::polynomial::cancel;
+    use crate::rational::rational::Rational;
 
     #[derive(Debug, Copy, Clone, PartialEq)]
     pub enum EntityKind {
@@ -8,144 +12,324 @@ pub mod entity {
         Variable,
         Function,
     }
-    pub trait Entity: DynClone {
+    pub trait Entity: DynClone + 'static {
         fn to_str(&self) -> String;
         fn differentiate(&self) -> Box<dyn Entity>;
+        fn differentiate_wrt(&self, state: &DataState) -> Box<dyn Entity>;
+        fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError>;
+        // every occurrence of `var` replaced by `replacement`, collapsed again
+        fn substitute(&self, var: &VariableIdentifier, replacement: &dyn Entity) -> Box<dyn Entity>;
         fn get_kind(&self) -> EntityKind;
-        fn as_any(&self) -> &dyn Any;
         fn collapse(&mut self);
+        // The expression tree behind this entity
+        fn to_expr(&self) -> Expr;
     }
-    
-    dyn_clone::clone_trait_object!(Entity);
-
-    pub fn try_cast_to<'a, T>(en: &Box<&'a (dyn Any + 'static)>) -> Option<&'a T> where T: Entity + 'static {
-        let ret = en.downcast_ref::<T>().unwrap();
 
-        Some(ret)
-    }
+    dyn_clone::clone_trait_object!(Entity);
 
-    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
     pub struct VariableIdentifier {
         name: String,
     }
 
-    #[derive(Debug, Clone, Eq)]
+    impl VariableIdentifier {
+        pub fn new(name: &str) -> Self {
+            Self { name: name.to_string() }
+        }
+
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    // Ordered by name first, then by power
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
     pub struct VariableEntity {
         variable: VariableIdentifier,
-        power: i32,
+        power: Rational,
+    }
+
+    impl VariableEntity {
+        pub fn new(variable: VariableIdentifier, power: Rational) -> Self {
+            Self { variable, power }
+        }
+
+        pub fn variable(&self) -> &VariableIdentifier {
+            &self.variable
+        }
+
+        pub fn power(&self) -> &Rational {
+            &self.power
+        }
     }
 
-    impl ToString for VariableEntity {
-        fn to_string(&self) -> String {
-            if self.power != 1 {
-                format!("{}^{}", self.variable.name, self.power)
+    impl fmt::Display for VariableEntity {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            if !self.power.is_integer() {
+                write!(f, "{}^({})", self.variable.name, self.power)
+            }
+            else if self.power != Rational::one() {
+                write!(f, "{}^{}", self.variable.name, self.power)
             }
             else {
-                format!("{}", self.variable.name)
+                write!(f, "{}", self.variable.name)
             }
         }
     }
-    impl PartialEq for VariableEntity {
-        fn eq(&self, other: &Self) -> bool {
-            self.variable.name.eq(&other.variable.name)
-            &&
-            self.power == other.power
+
+    ///--- State ---///
+    pub struct DataState {
+        variable_wrt: VariableIdentifier,
+    }
+
+    impl DataState {
+        pub fn new(variable_wrt: VariableIdentifier) -> Self {
+            Self { variable_wrt }
         }
 
-        fn ne(&self, other: &Self) -> bool {
-            self.variable.name.ne(&other.variable.name)
-            &&
-            self.power != other.power
+        pub fn variable_wrt(&self) -> &VariableIdentifier {
+            &self.variable_wrt
         }
     }
-    impl PartialOrd for VariableEntity {
-        fn ge(&self, other: &Self) -> bool {
-            self.power.ge(&other.power)
-        }
+    ////// State //////
+
+
+    //---- Evaluation ----//
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum EvalError {
+        UnboundVariable(VariableIdentifier),
+        DivisionByZero,
+        // a function applied outside of its domain, e.g. ln(-1)
+        Domain { function: String, argument: f64 },
+    }
 
-        fn gt(&self, other: &Self) -> bool {
-            self.power.gt(&other.power)
+    impl fmt::Display for EvalError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                EvalError::UnboundVariable(variable) => write!(f, "no value bound to '{}'", variable.name),
+                EvalError::DivisionByZero => write!(f, "division by zero"),
+                EvalError::Domain { function, argument } => write!(f, "{} is undefined at {}", function, argument),
+            }
         }
+    }
+    impl std::error::Error for EvalError {}
 
-        fn le(&self, other: &Self) -> bool {
-            self.power.le(&other.power)
+    fn evaluate_variable(var: &VariableEntity, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+        match env.get(&var.variable) {
+            Some(&value) => raise(value, var.power.to_f64()),
+            None => Err(EvalError::UnboundVariable(var.variable.clone())),
         }
+    }
 
-        fn lt(&self, other: &Self) -> bool {
-            self.power.lt(&other.power)
+    fn raise(base: f64, exponent: f64) -> Result<f64, EvalError> {
+        if base == 0.0 && exponent < 0.0 {
+            return Err(EvalError::DivisionByZero);
         }
+        checked("^", base, base.powf(exponent))
+    }
 
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-            self.power.partial_cmp(&other.power)
+    // turns the NaN or infinity a function returned into an error about its argument
+    fn checked(function: &str, argument: f64, value: f64) -> Result<f64, EvalError> {
+        if value.is_finite() {
+            Ok(value)
         }
+        else {
+            Err(EvalError::Domain { function: function.to_string(), argument })
+        }
+    }
+    ////// Evaluation //////
+
+
+    //---- Expressions ----//
+    // An expression tree. Nodes are shared behind reference counts, so clones are cheap and
+    // only the path to a node that changes is copied. Equality, ordering and hashing are
+    // structural, and order expressions of different kinds in the order of the variants
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum Expr {
+        Constant(Rc<ConstantTerm>),
+        Variable(Rc<VariableTerm>),
+        Sum(Rc<SummationFunction>),
+        Product(Rc<MultiplicationFunction>),
+        Quotient(Rc<DivisionFunction>),
+        Power(Rc<PowerFunction>),
+        Composition(Rc<CompositionFunction>),
+        Named(NamedConstant),
     }
-    impl Ord for VariableEntity {
-        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            self.variable.name.cmp(&other.variable.name)
+
+    // Behaviour every node of an expression provides
+    pub trait ExprNode {
+        fn to_str(&self) -> String;
+        fn differentiate(&self) -> Expr;
+        fn differentiate_wrt(&self, state: &DataState) -> Expr;
+        fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError>;
+        fn substitute(&self, var: &VariableIdentifier, replacement: &Expr) -> Expr;
+        fn collapse(&mut self);
+
+        // The expression this node stands for once collapsed, when that is another kind of node (e.g. `f*1` is `f`)
+        fn reduce(&self) -> Option<Expr> {
+            None
         }
+    }
 
-        fn max(self, other: Self) -> Self
-            where
-                Self: Sized, {
-            let mstr = self.variable.name.clone().max(other.variable.name.clone());
-            if mstr == self.variable.name {
-                self
+    impl Expr {
+        fn node(&self) -> &dyn ExprNode {
+            match self {
+                Expr::Constant(node) => &**node,
+                Expr::Variable(node) => &**node,
+                Expr::Sum(node) => &**node,
+                Expr::Product(node) => &**node,
+                Expr::Quotient(node) => &**node,
+                Expr::Power(node) => &**node,
+                Expr::Composition(node) => &**node,
+                Expr::Named(node) => node,
             }
-            else {
-                other
+        }
+
+        fn node_mut(&mut self) -> &mut dyn ExprNode {
+            match self {
+                Expr::Constant(node) => unshared(node),
+                Expr::Variable(node) => unshared(node),
+                Expr::Sum(node) => unshared(node),
+                Expr::Product(node) => unshared(node),
+                Expr::Quotient(node) => unshared(node),
+                Expr::Power(node) => unshared(node),
+                Expr::Composition(node) => unshared(node),
+                Expr::Named(node) => node,
             }
         }
 
-        fn min(self, other: Self) -> Self
-            where
-                Self: Sized, {
-            let mstr = self.variable.name.clone().min(other.variable.name.clone());
-            if mstr == self.variable.name {
-                self
+        pub fn to_str(&self) -> String {
+            self.node().to_str()
+        }
+
+        pub fn differentiate(&self) -> Expr {
+            self.node().differentiate()
+        }
+
+        pub fn differentiate_wrt(&self, state: &DataState) -> Expr {
+            self.node().differentiate_wrt(state)
+        }
+
+        pub fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            self.node().evaluate(env)
+        }
+
+        // every occurrence of `var` replaced by `replacement`, collapsed again
+        pub fn substitute(&self, var: &VariableIdentifier, replacement: &Expr) -> Expr {
+            self.node().substitute(var, replacement)
+        }
+
+        pub fn get_kind(&self) -> EntityKind {
+            match self {
+                Expr::Constant(_) => EntityKind::Constant,
+                Expr::Variable(_) => EntityKind::Variable,
+                // named constants are kept opaque so that they are never folded into a numeric ConstantTerm
+                Expr::Sum(_) | Expr::Product(_) | Expr::Quotient(_) | Expr::Power(_) | Expr::Composition(_) | Expr::Named(_) => EntityKind::Function,
             }
-            else {
-                other
+        }
+
+        pub fn collapse(&mut self) {
+            let node = self.node_mut();
+            node.collapse();
+            if let Some(reduced) = node.reduce() {
+                *self = reduced;
             }
         }
-        
     }
 
-    ///--- State ---///
-    pub struct DataState {
-        variable_wrt: VariableIdentifier,
+    // the node behind `node`, copied first when other expressions share it
+    fn unshared<T: ExprNode + Clone + 'static>(node: &mut Rc<T>) -> &mut dyn ExprNode {
+        Rc::<T>::make_mut(node)
     }
 
-    impl DataState {
-        pub fn new(variable_wrt: VariableIdentifier) -> Self {
-            Self { variable_wrt: variable_wrt }
+    impl Entity for Expr {
+        fn to_str(&self) -> String {
+            Expr::to_str(self)
+        }
+
+        fn differentiate(&self) -> Box<dyn Entity> {
+            Box::new(Expr::differentiate(self))
+        }
+
+        fn differentiate_wrt(&self, state: &DataState) -> Box<dyn Entity> {
+            Box::new(Expr::differentiate_wrt(self, state))
+        }
+
+        fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            Expr::evaluate(self, env)
+        }
+
+        fn substitute(&self, var: &VariableIdentifier, replacement: &dyn Entity) -> Box<dyn Entity> {
+            Box::new(Expr::substitute(self, var, &replacement.to_expr()))
+        }
+
+        fn get_kind(&self) -> EntityKind {
+            Expr::get_kind(self)
+        }
+
+        fn collapse(&mut self) {
+            Expr::collapse(self)
+        }
+
+        fn to_expr(&self) -> Expr {
+            self.clone()
         }
     }
-    ////// State //////
+
+    macro_rules! expr_from {
+        ($($node:ident => $variant:ident),*) => {$(
+            impl From<$node> for Expr {
+                fn from(node: $node) -> Expr {
+                    Expr::$variant(Rc::new(node))
+                }
+            }
+        )*};
+    }
+    expr_from!(
+        ConstantTerm => Constant,
+        VariableTerm => Variable,
+        SummationFunction => Sum,
+        MultiplicationFunction => Product,
+        DivisionFunction => Quotient,
+        PowerFunction => Power,
+        CompositionFunction => Composition
+    );
+
+    impl From<NamedConstant> for Expr {
+        fn from(constant: NamedConstant) -> Expr {
+            Expr::Named(constant)
+        }
+    }
+    ////// Expressions //////
 
 
     //---- Terms ----//
-    #[derive(Debug, Clone)]
-    pub struct ConstantTerm<'a> {
-        value: f64,
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct ConstantTerm {
+        value: Rational,
         non_wrt_variables: Vec<VariableEntity>,
-        kind: EntityKind,
-        _phantom_data: std::marker::PhantomData<&'a ()>,
     }
 
-    #[derive(Clone)]    
-    pub struct VariableTerm<'a> {
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct VariableTerm {
         variable: VariableEntity,
-        coeffs: Vec<Box<dyn Entity>>,
-        kind: EntityKind,
-        _phantom_data: std::marker::PhantomData<&'a ()>,
+        coeffs: Vec<Expr>,
     }
     pub trait TermEntity {
-        fn compute_result<'a>(&self) -> VariableTerm<'a>;
+        fn compute_result(&self) -> VariableTerm;
     }
 
-    impl ConstantTerm<'static> {
-        pub fn new(value: f64, non_wrt_variables: Vec<VariableEntity>) -> Self {
-            Self { value: value, non_wrt_variables: non_wrt_variables, _phantom_data: PhantomData, kind: EntityKind::Constant }
+    impl ConstantTerm {
+        pub fn new(value: Rational, non_wrt_variables: Vec<VariableEntity>) -> Self {
+            Self { value, non_wrt_variables }
+        }
+
+        pub fn value(&self) -> &Rational {
+            &self.value
+        }
+
+        pub fn non_wrt_variables(&self) -> &[VariableEntity] {
+            &self.non_wrt_variables
         }
 
         pub fn can_add_if_collapsed(&self, other: &Self) -> bool {
@@ -153,58 +337,70 @@ pub mod entity {
         }
 
         pub fn add(&self, other: &Self) -> Self {
-            Self::new(self.value + other.value, self.non_wrt_variables.clone())
+            Self::new(&self.value + &other.value, self.non_wrt_variables.clone())
         }
-        
+
         pub fn multiply(&self, other: &Self) -> Self {
             let mut vars = self.non_wrt_variables.clone();
-            for i in 0..vars.len() {
-                vars[i].power += other.non_wrt_variables[i].power;
-            }
+            vars.extend(other.non_wrt_variables.iter().cloned());
 
-            Self::new(self.value * other.value, vars)
+            let mut product = Self::new(&self.value * &other.value, vars);
+            product.collapse();
+            product
         }
     }
-    impl Entity for ConstantTerm<'static> {
+    impl ExprNode for ConstantTerm {
         fn to_str(&self) -> String {
-            let mut str = String::new();
-
-            if self.value == 0.0 {
+            if self.value.is_zero() {
                 return "0".to_string();
             }
 
-            str += &self.value.to_string();
-            str += "*";
-
-            for var in &self.non_wrt_variables {
-                str += &var.to_string();
-                str += "*"
-            };
+            let factors = self.non_wrt_variables.iter().map(|var| var.to_string()).collect();
+            format_product(&self.value, factors)
+        }
 
-            str.truncate(str.len() - 1);
-            str
+        fn differentiate(&self) -> Expr {
+            create_number(0)
         }
 
-        fn differentiate(&self) -> Box<dyn Entity> {
-            Box::new(ConstantTerm::new(0.0, Vec::new()))
+        fn differentiate_wrt(&self, state: &DataState) -> Expr {
+            let mut collapsed = self.clone();
+            collapsed.collapse();
+
+            let position = collapsed.non_wrt_variables.iter().position(|var| var.variable == state.variable_wrt);
+            match position {
+                Some(i) => {
+                    collapsed.value = &collapsed.value * &collapsed.non_wrt_variables[i].power;
+                    collapsed.non_wrt_variables[i].power = &collapsed.non_wrt_variables[i].power - &Rational::one();
+                    collapsed.collapse();
+                    collapsed.into()
+                },
+                None => create_number(0),
+            }
         }
-        
-        fn get_kind(&self) -> EntityKind {
-            self.kind
+
+        fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            let mut value = self.value.to_f64();
+            for var in &self.non_wrt_variables {
+                value *= evaluate_variable(var, env)?;
+            }
+            Ok(value)
         }
 
-        fn as_any(&self) -> &dyn Any {
-            self
+        fn substitute(&self, var: &VariableIdentifier, replacement: &Expr) -> Expr {
+            let mut factors = vec![create_number(self.value.clone())];
+            factors.extend(self.non_wrt_variables.iter().map(|v| substitute_variable(v, var, replacement)));
+            collapsed_product(factors)
         }
-        
+
         fn collapse(&mut self) {
-            let mut vars: HashMap<String, i32> = HashMap::new();
+            let mut vars: HashMap<String, Rational> = HashMap::new();
 
             let list = self.non_wrt_variables.to_owned();
             for var in list.into_iter() {
                 if vars.contains_key(&var.variable.name) {
                     let prev = vars.get(&var.variable.name).unwrap();
-                    vars.insert(var.variable.name, prev+var.power);
+                    vars.insert(var.variable.name, prev + &var.power);
                 }
                 else {
                     vars.insert(var.variable.name, var.power);
@@ -213,7 +409,10 @@ pub mod entity {
 
             let mut new_list:Vec<VariableEntity> = vec![];
             for (name, power) in vars.into_iter() {
-                new_list.push(VariableEntity { variable: VariableIdentifier { name: name }, power: power });
+                if power.is_zero() || self.value.is_zero() {
+                    continue;
+                }
+                new_list.push(VariableEntity { variable: VariableIdentifier { name }, power });
             }
 
             new_list.sort();
@@ -221,187 +420,201 @@ pub mod entity {
             self.non_wrt_variables = new_list;
         }
     }
-    impl TermEntity for ConstantTerm<'static> {
-        fn compute_result<'a>(&self) -> VariableTerm<'a> {
-            let variable = VariableEntity { variable: VariableIdentifier { name: "".to_string() }, power: 0 };
-            
-            let coeffs:Vec<Box<dyn Entity + 'static>> = vec![Box::new(ConstantTerm::new(self.value, self.non_wrt_variables.clone()))];
+    impl TermEntity for ConstantTerm {
+        fn compute_result(&self) -> VariableTerm {
+            let variable = VariableEntity { variable: VariableIdentifier { name: "".to_string() }, power: Rational::zero() };
+
+            let coeffs = vec![ConstantTerm::new(self.value.clone(), self.non_wrt_variables.clone()).into()];
             VariableTerm::new(variable, coeffs)
         }
     }
 
-    impl VariableTerm<'static> {
-        pub fn new(variable: VariableEntity, coeffs: Vec<Box<dyn Entity>>) -> Self {
-            Self { variable: variable, coeffs: coeffs, kind:EntityKind::Variable, _phantom_data: PhantomData }
+    impl VariableTerm {
+        pub fn new(variable: VariableEntity, coeffs: Vec<Expr>) -> Self {
+            Self { variable, coeffs }
         }
-        
-        pub fn equal_coeffs(&self, other: &Self) -> bool {
-            for i in 0..self.coeffs.len() {
-                if self.coeffs[i].get_kind() == EntityKind::Constant {
-                    continue;
-                }
 
-                if self.coeffs[i].get_kind() == other.coeffs[i].get_kind() {
-                    let equal = match self.coeffs[i].get_kind() {
-                        EntityKind::Constant => { false },
-                        EntityKind::Variable => {
-                            let first = try_cast_to::<VariableTerm>(&Box::new(self.coeffs[i].as_any())).unwrap();
-                            let second = try_cast_to::<VariableTerm>(&Box::new(other.coeffs[i].as_any())).unwrap();
+        pub fn variable(&self) -> &VariableEntity {
+            &self.variable
+        }
 
-                            first.can_add_if_collapsed(second)
-                        },
-                        EntityKind::Function => {
-                            true
-                        },
-                    };
+        pub fn coeffs(&self) -> &[Expr] {
+            &self.coeffs
+        }
 
-                    if !equal { return false }
-                }
+        pub fn equal_coeffs(&self, other: &Self) -> bool {
+            if self.coeffs.len() != other.coeffs.len() {
+                return false;
+            }
+
+            for (first, second) in self.coeffs.iter().zip(other.coeffs.iter()) {
+                let equal = match (first, second) {
+                    (Expr::Constant(first), Expr::Constant(second)) => first.can_add_if_collapsed(second),
+                    (Expr::Variable(first), Expr::Variable(second)) => first.can_add_if_collapsed(second),
+                    (Expr::Constant(_) | Expr::Variable(_), _) | (_, Expr::Constant(_) | Expr::Variable(_)) => false,
+                    _ => first == second,
+                };
+
+                if !equal { return false }
             };
 
             true
         }
 
         pub fn can_add_if_collapsed(&self, other: &Self) -> bool {
-            self.variable == other.variable 
+            self.variable == other.variable
             &&
             self.equal_coeffs(other)
         }
-        
+
         pub fn add(&self, other: &Self) -> Self {
             let mut coeffs = self.coeffs.clone();
-            coeffs.pop();
-            let constant1 = try_cast_to::<ConstantTerm>(&Box::new(self.coeffs.last().unwrap().as_any())).unwrap();
-            let constant2 = try_cast_to::<ConstantTerm>(&Box::new(other.coeffs.last().unwrap().as_any())).unwrap();
-            let constant_sum = constant1.add(constant2);
-            coeffs.push(Box::new(constant_sum));
+            if matches!(coeffs.last(), Some(Expr::Constant(_))) {
+                coeffs.pop();
+            }
+            coeffs.push(self.constant().add(&other.constant()).into());
 
             Self::new(self.variable.clone(), coeffs)
         }
-        
-        pub fn multiply(&self, other: &Self) -> Self {
-            let mut constant_product = create_number(1.0);
-            let mut coeff_product: Vec<Box<dyn Entity>> = vec![];
-            let mut var_product = VariableEntity { variable: VariableIdentifier { name: self.variable.variable.name.clone() }, power: 0 };
-
-            for coeff in other.coeffs.iter() {
-                match coeff.get_kind() {
-                    EntityKind::Constant => {
-                        let c = try_cast_to::<ConstantTerm>(&Box::new(coeff.as_any())).unwrap();
-                        constant_product = Box::new(constant_product.multiply(c));
-                    },
-                    EntityKind::Variable => {
-                        let v = try_cast_to::<VariableTerm>(&Box::new(coeff.as_any())).unwrap();
-                        var_product.power += v.variable.power;
-                    }
-                    EntityKind::Function => {
-                        coeff_product.push(coeff.clone());
-                    },
-
-                }
-            };
 
-            var_product.power += self.variable.power + other.variable.power;
+        pub fn multiply(&self, other: &Self) -> Self {
+            let mut coeffs = self.coeffs.clone();
+            coeffs.push(other.clone().into());
 
-            coeff_product.push(constant_product);
+            let mut product = Self::new(self.variable.clone(), coeffs);
+            product.collapse();
+            product
+        }
 
-            Self::new(var_product, coeff_product)
+        // the numeric coefficient, which collapsing leaves as the last of the coeffs
+        fn constant(&self) -> ConstantTerm {
+            match self.coeffs.last() {
+                Some(Expr::Constant(constant)) => (**constant).clone(),
+                _ => ConstantTerm::new(Rational::one(), vec![]),
+            }
         }
     }
-    impl Entity for VariableTerm<'static> { 
+    impl ExprNode for VariableTerm {
         fn to_str(&self) -> String {
-            let mut str = String::new();
-            
-            let mut added = false;
-            for var in &self.coeffs {
-                let s = var.to_str();
-                if s == "1" {
+            let mut constant = ConstantTerm::new(Rational::one(), vec![]);
+            let mut functions: Vec<String> = vec![];
+
+            for coeff in &self.coeffs {
+                if let Expr::Constant(c) = coeff {
+                    constant = constant.multiply(c);
                     continue;
                 }
-                else if s.starts_with("0") {
+
+                let s = parenthesize(coeff);
+                if s == "0" {
                     return "0".to_string();
                 }
-                str += &s;
-                str += "*";
-                added = true;
+                else if s != "1" {
+                    functions.push(s);
+                }
             };
 
-            str.truncate(str.len() - (if added { 1 } else { 0 }));
+            if constant.value.is_zero() {
+                return "0".to_string();
+            }
 
-            str += "";
-            str += &self.variable.to_string();
+            let mut factors: Vec<String> = vec![];
+            if !self.variable.power.is_zero() {
+                factors.push(self.variable.to_string());
+            }
+            factors.extend(constant.non_wrt_variables.iter().map(|var| var.to_string()));
+            factors.extend(functions);
 
-            str
+            format_product(&constant.value, factors)
         }
 
-        fn differentiate(&self) -> Box<dyn Entity> {
-            let power = self.variable.power - 1;
-            let variable = VariableEntity { variable: { VariableIdentifier { name: self.variable.variable.name.clone() } }, power: power};
-            let mut coeffs: Vec<Box<dyn Entity>> = vec![];
-            for c in self.coeffs.iter() {
-                coeffs.push(dyn_clone::clone_box(&**c))
-            }
+        fn differentiate(&self) -> Expr {
+            let power = &self.variable.power - &Rational::one();
+            let variable = VariableEntity { variable: { VariableIdentifier { name: self.variable.variable.name.clone() } }, power};
+            let mut coeffs = self.coeffs.clone();
 
-            coeffs.insert(0, Box::new(ConstantTerm::new(self.variable.power.into(), vec![])));
+            coeffs.insert(0, ConstantTerm::new(self.variable.power.clone(), vec![]).into());
 
-            Box::new(VariableTerm::new(variable, coeffs))
-        }
-        
-        fn get_kind(&self) -> EntityKind {
-            self.kind
-        }
-        
-        fn as_any(&self) -> &dyn Any {
-            self
+            VariableTerm::new(variable, coeffs).into()
         }
-        
-        fn collapse(&mut self) {
-            let mut new_list: Vec<Box<dyn Entity>> = vec![];
-            let mut constant_value: Option<ConstantTerm> = None;
 
-            for coeff in &mut self.coeffs.iter_mut() {
-                coeff.collapse();
+        fn differentiate_wrt(&self, state: &DataState) -> Expr {
+            let mut terms: Vec<Expr> = vec![];
 
-                if coeff.get_kind() == EntityKind::Variable {
-                    let v = try_cast_to::<VariableTerm>(&Box::new(coeff.as_any())).unwrap();
+            if self.variable.variable == state.variable_wrt {
+                terms.push(self.differentiate());
+            }
 
-                    if v.variable.variable.name == self.variable.variable.name {
-                        self.variable.power += v.variable.power;
-                    }
-                    else {
-                        panic!("entity::VariableTerm::collapse(): Cannot have more than 1 variables")
-                    }
-                    
-                    continue;
-                }
-                else if coeff.get_kind() == EntityKind::Constant {
-                    let casted_coeff = try_cast_to::<ConstantTerm>(&Box::new(coeff.as_any()));
-                    if constant_value.is_none() {
-                        constant_value = casted_coeff.cloned();
-                    }
-                    else {
-                        constant_value = Some(constant_value.unwrap().multiply(casted_coeff.unwrap()));
-                    }
+            // product rule over the coefficients, which may hold other variables
+            for (i, coeff) in self.coeffs.iter().enumerate() {
+                let dcoeff = coeff.differentiate_wrt(state);
+                if is_zero(&dcoeff) {
                     continue;
                 }
 
-                new_list.push(coeff.clone());
+                let mut coeffs = self.coeffs.clone();
+                coeffs[i] = dcoeff;
+                terms.push(VariableTerm::new(self.variable.clone(), coeffs).into());
             }
 
-            if constant_value.is_some() {
-                let mut unwraped = constant_value.unwrap();
-                unwraped.collapse();
-                new_list.push(Box::new(unwraped));
+            match terms.len() {
+                0 => create_number(0),
+                1 => terms.pop().unwrap(),
+                _ => SummationFunction::new(terms).into(),
             }
-            else {
-                new_list.push(create_number(1.0));
+        }
+
+        fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            let mut value = evaluate_variable(&self.variable, env)?;
+            for coeff in &self.coeffs {
+                value *= coeff.evaluate(env)?;
+            }
+            Ok(value)
+        }
+
+        fn substitute(&self, var: &VariableIdentifier, replacement: &Expr) -> Expr {
+            let mut factors = vec![substitute_variable(&self.variable, var, replacement)];
+            factors.extend(self.coeffs.iter().map(|coeff| coeff.substitute(var, replacement)));
+            collapsed_product(factors)
+        }
+
+        fn collapse(&mut self) {
+            let mut new_list: Vec<Expr> = vec![];
+            let mut constant_value = ConstantTerm::new(Rational::one(), vec![]);
+
+            let mut pending = std::mem::take(&mut self.coeffs);
+            let mut i = 0;
+            while i < pending.len() {
+                let mut coeff = pending[i].clone();
+                coeff.collapse();
+                i += 1;
+
+                match coeff {
+                    Expr::Variable(v) => {
+                        // other variables are constants with respect to this term's variable
+                        if v.variable.variable == self.variable.variable {
+                            self.variable.power = &self.variable.power + &v.variable.power;
+                        }
+                        else {
+                            constant_value = constant_value.multiply(&ConstantTerm::new(Rational::one(), vec![v.variable.clone()]));
+                        }
+                        pending.extend(v.coeffs.iter().cloned());
+                    },
+                    Expr::Constant(c) => {
+                        constant_value = constant_value.multiply(&c);
+                    },
+                    Expr::Sum(_) | Expr::Product(_) | Expr::Quotient(_) | Expr::Power(_) | Expr::Composition(_) | Expr::Named(_) => {
+                        new_list.push(coeff);
+                    },
+                }
             }
 
+            new_list.push(constant_value.into());
             self.coeffs = new_list;
         }
     }
-    impl TermEntity for VariableTerm<'static> {
-        fn compute_result<'a>(&self) -> VariableTerm<'a> {
+    impl TermEntity for VariableTerm {
+        fn compute_result(&self) -> VariableTerm {
             self.clone()
         }
     }
@@ -409,250 +622,497 @@ pub mod entity {
 
 
     //---- Functions ----//
-    #[derive(Clone)]
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
     pub struct SummationFunction {
-        terms: Vec<Box<dyn Entity>>,
-        kind: EntityKind,
+        terms: Vec<Expr>,
     }
-    
-    #[derive(Clone)]
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
     pub struct MultiplicationFunction {
-        first: Box<dyn Entity>,
-        second: Box<dyn Entity>,
-        kind: EntityKind,
+        first: Expr,
+        second: Expr,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct DivisionFunction {
+        numerator: Expr,
+        denominator: Expr,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct PowerFunction {
+        base: Expr,
+        exponent: Expr,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct CompositionFunction {
+        outer: ElementaryFunction,
+        inner: Expr,
     }
 
     pub trait Function {
-        
+
+    }
+
+    // An outer function f applied by a CompositionFunction to its inner expression u
+    pub trait UnaryFunction {
+        fn name(&self) -> String;
+        // f'(u), still to be multiplied by u' through the chain rule
+        fn derivative(&self, inner: &Expr) -> Expr;
+        fn apply(&self, value: f64) -> f64;
+
+        // f(u) as a simpler expression for special values of u, e.g. ln(1) = 0
+        fn simplify(&self, _inner: &Expr) -> Option<Expr> {
+            None
+        }
     }
 
 
     impl SummationFunction {
-        pub fn new(terms: Vec<Box<dyn Entity>>) -> Self {
-            Self {
-                terms: terms,
-                kind: EntityKind::Function
-            }
+        pub fn new(terms: Vec<Expr>) -> Self {
+            Self { terms }
+        }
+
+        pub fn terms(&self) -> &[Expr] {
+            &self.terms
         }
     }
 
     impl MultiplicationFunction {
-        pub fn new(first: Box<dyn Entity>, second: Box<dyn Entity>) -> Self {
-            Self {
-                first: first, 
-                second: second,
-                kind: EntityKind::Function
-            }
+        pub fn new(first: Expr, second: Expr) -> Self {
+            Self { first, second }
         }
-    }
-    ////// Functions //////
 
+        pub fn first(&self) -> &Expr {
+            &self.first
+        }
 
-    //---- Helper Methods ----//
-    pub fn create_number(number: f64) -> Box<ConstantTerm<'static>> {
-        Box::new(ConstantTerm::new(number, vec![]))
-    }
-    
-    pub fn create_variable<'a>(name: &str, power: i32) -> Box<VariableTerm<'a>> {
-        Box::new(VariableTerm::new(VariableEntity { variable: VariableIdentifier { name: name.to_string() }, power: power }, vec![]))
+        pub fn second(&self) -> &Expr {
+            &self.second
+        }
     }
-    ////// Helper Methods //////
 
+    impl DivisionFunction {
+        pub fn new(numerator: Expr, denominator: Expr) -> Self {
+            Self { numerator, denominator }
+        }
 
-    //---- Differentiation ----//
-    impl Entity for SummationFunction {
-        fn to_str(&self) -> String {
-            let mut str = String::new();
+        pub fn numerator(&self) -> &Expr {
+            &self.numerator
+        }
 
-            let mut added = false;
-            for var in &self.terms {
-                let s = var.to_str();
-                if s.starts_with("0") {
-                    continue;
-                }
-                str += &s;
-                str += " + ";
-                added = true;
-            };
+        pub fn denominator(&self) -> &Expr {
+            &self.denominator
+        }
+    }
 
-            str.truncate(str.len() - (if added { 3 } else { 0 }));
-            str
+    impl PowerFunction {
+        pub fn new(base: Expr, exponent: Expr) -> Self {
+            Self { base, exponent }
         }
 
-        fn differentiate(&self) -> Box<dyn Entity> {
-            let mut scloned = self.clone();
-            scloned.collapse();
+        pub fn base(&self) -> &Expr {
+            &self.base
+        }
 
-            let mut sum: Vec<Box<dyn Entity>> = vec![];
-            for term in &scloned.terms {
-                sum.push(term.differentiate())
-            };
-            scloned.terms = sum;
+        pub fn exponent(&self) -> &Expr {
+            &self.exponent
+        }
+    }
 
-            scloned.collapse();
-            
-            Box::new(scloned)
+    impl CompositionFunction {
+        pub fn new(outer: ElementaryFunction, inner: Expr) -> Self {
+            Self { outer, inner }
         }
-        
-        fn get_kind(&self) -> EntityKind {
-            self.kind
+
+        pub fn outer(&self) -> &ElementaryFunction {
+            &self.outer
         }
-        
-        fn as_any(&self) -> &dyn Any {
-            self
+
+        pub fn inner(&self) -> &Expr {
+            &self.inner
         }
-    
-        fn collapse(&mut self) {
-            for term in self.terms.iter_mut() {
-                term.collapse();
-            }
-            
-            let mut did_change = true;
-            let mut change_i = 0;
-            let mut change_j = 0;
-            while did_change {
-                did_change = false;
-
-                let mut sum: Option<Box<dyn Entity>> = None;
-                for (i, first) in self.terms.iter().enumerate() {
-                    for (j, second) in self.terms.iter().enumerate() {
-                        if i == j {
-                            continue
-                        }
-    
-                        //--- Check if we can add ---//
-                        if first.get_kind() != second.get_kind() {
-                            continue;
-                        }
-    
-                        sum = match first.get_kind() {
-                            EntityKind::Constant => {
-                                let firstc = try_cast_to::<ConstantTerm>(&Box::new(first.as_any())).unwrap();
-                                let secondc = try_cast_to::<ConstantTerm>(&Box::new(second.as_any())).unwrap();
-    
-                                if firstc.can_add_if_collapsed(secondc) {
-                                    Some(Box::new(firstc.add(secondc)))
-                                }
-                                else {
-                                    None
-                                }
-                            },
-                            EntityKind::Variable => {
-                                let firstc = try_cast_to::<VariableTerm>(&Box::new(first.as_any())).unwrap();
-                                let secondc = try_cast_to::<VariableTerm>(&Box::new(second.as_any())).unwrap();
-    
-                                if firstc.can_add_if_collapsed(secondc) {
-                                    Some(Box::new(firstc.add(secondc)))
-                                }
-                                else {
-                                    None
-                                }
-                            },
-                            EntityKind::Function => {
-                                None
-                            }
-                        };
-
-                        if sum.is_some() {
-                            change_i = i;
-                            change_j = j;
-                            did_change = true;
-                        }
-                        break;
-                    }
-                }
+    }
+    ////// Functions //////
 
-                if did_change {
-                    self.terms.swap_remove(change_i);
-                    self.terms.swap_remove(change_j);
-                    self.terms.insert(0, sum.unwrap())
-                }
-            }
 
+    //---- Helper Methods ----//
+    pub fn create_number(number: impl Into<Rational>) -> Expr {
+        ConstantTerm::new(number.into(), vec![]).into()
+    }
 
-        }
+    pub fn create_variable(name: &str, power: impl Into<Rational>) -> Expr {
+        VariableTerm::new(VariableEntity { variable: VariableIdentifier { name: name.to_string() }, power: power.into() }, vec![]).into()
     }
-    impl Function for SummationFunction {
 
+    // The value of a plain number, i.e. a ConstantTerm without non-wrt variables
+    pub fn as_number(entity: &Expr) -> Option<Rational> {
+        let Expr::Constant(constant) = entity else {
+            return None;
+        };
+        if !constant.value.is_zero() && !constant.non_wrt_variables.is_empty() {
+            return None;
+        }
+        Some(constant.value.clone())
     }
 
-    impl Entity for MultiplicationFunction {
-        fn to_str(&self) -> String {
-            let mut str = String::new();
+    // `var` itself, or `replacement` to its power when `var` is the variable being substituted
+    fn substitute_variable(var: &VariableEntity, target: &VariableIdentifier, replacement: &Expr) -> Expr {
+        if var.variable != *target {
+            return create_variable(&var.variable.name, var.power.clone());
+        }
 
-            let s1 = self.first.to_str();
-            let s2 = self.second.to_str();
+        let mut power: Expr = PowerFunction::new(replacement.clone(), create_number(var.power.clone())).into();
+        power.collapse();
+        power
+    }
 
-            if s1 == "1" {
-                str += &s2;
+    fn collapsed_product(factors: Vec<Expr>) -> Expr {
+        let mut product = factors.into_iter().reduce(|product, factor| MultiplicationFunction::new(product, factor).into()).unwrap_or_else(|| create_number(1));
+        product.collapse();
+        product
+    }
+
+    fn is_zero(entity: &Expr) -> bool {
+        entity.to_str() == "0"
+    }
+
+    fn parenthesize(entity: &Expr) -> String {
+        let s = entity.to_str();
+        if matches!(entity, Expr::Sum(_)) && s.contains(' ') {
+            format!("({})", s)
+        }
+        else {
+            s
+        }
+    }
+
+    fn is_atomic(s: &str) -> bool {
+        // a coefficient glued to what it multiplies, as in 4x^(1/2) or 2sin(x), is a product
+        let glued = s.starts_with(|c: char| c.is_ascii_digit()) && s.contains(char::is_alphabetic);
+        if glued {
+            return false;
+        }
+
+        // a single call such as ln(x + 1), or a variable to a fractional power such as x^(1/2)
+        if let (Some(open), true) = (s.find('('), s.ends_with(')')) {
+            let mut depth = 0;
+            let closes_at_end = s[open..].char_indices().all(|(i, c)| {
+                depth += match c { '(' => 1, ')' => -1, _ => 0 };
+                depth > 0 || open + i == s.len() - 1
+            });
+            let name = s[..open].strip_suffix('^').unwrap_or(&s[..open]);
+            return closes_at_end && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+        }
+
+        s.matches('^').count() <= 1
+        &&
+        s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '^')
+    }
+
+    fn format_product(value: &Rational, factors: Vec<String>) -> String {
+        let product = factors.join("*");
+        if product.is_empty() {
+            return value.to_string();
+        }
+
+        if *value == Rational::one() {
+            product
+        }
+        else if *value == -Rational::one() {
+            format!("-{}", product)
+        }
+        else if product.starts_with('(') || !value.is_integer() {
+            format!("{}*{}", value, product)
+        }
+        else {
+            format!("{}{}", value, product)
+        }
+    }
+    ////// Helper Methods //////
+
+
+    //---- Differentiation ----//
+    impl SummationFunction {
+        fn differentiate_by(&self, d: &dyn Fn(&Expr) -> Expr) -> Expr {
+            let mut scloned = self.clone();
+            scloned.collapse();
+
+            let mut sum: Vec<Expr> = vec![];
+            for term in &scloned.terms {
+                sum.push(d(term))
+            };
+            scloned.terms = sum;
+
+            scloned.collapse();
+
+            scloned.into()
+        }
+
+        fn try_add(first: &Expr, second: &Expr) -> Option<Expr> {
+            match (first, second) {
+                (Expr::Constant(firstc), Expr::Constant(secondc)) => {
+                    if firstc.can_add_if_collapsed(secondc) {
+                        Some(firstc.add(secondc).into())
+                    }
+                    else {
+                        None
+                    }
+                },
+                (Expr::Variable(firstc), Expr::Variable(secondc)) => {
+                    if firstc.can_add_if_collapsed(secondc) {
+                        Some(firstc.add(secondc).into())
+                    }
+                    else {
+                        None
+                    }
+                },
+                _ => None,
             }
-            else if s2 == "1" {
-                str += &s1;
+        }
+    }
+    impl ExprNode for SummationFunction {
+        fn to_str(&self) -> String {
+            let mut str = String::new();
+
+            for var in &self.terms {
+                let s = var.to_str();
+                if s == "0" {
+                    continue;
+                }
+
+                if str.is_empty() {
+                    str += &s;
+                }
+                else if let (Some(negated), false) = (s.strip_prefix('-'), matches!(var, Expr::Sum(_))) {
+                    str += " - ";
+                    str += negated;
+                }
+                else {
+                    str += " + ";
+                    str += &s;
+                }
+            };
+
+            if str.is_empty() {
+                return "0".to_string();
             }
-            else {
-                str += &self.first.to_str();
-                str += "*";
-                str += &self.second.to_str();
+            str
+        }
+
+        fn differentiate(&self) -> Expr {
+            self.differentiate_by(&|term| term.differentiate())
+        }
+
+        fn differentiate_wrt(&self, state: &DataState) -> Expr {
+            self.differentiate_by(&|term| term.differentiate_wrt(state))
+        }
+
+        fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            let mut sum = 0.0;
+            for term in &self.terms {
+                sum += term.evaluate(env)?;
             }
+            Ok(sum)
+        }
 
-            str
+        fn substitute(&self, var: &VariableIdentifier, replacement: &Expr) -> Expr {
+            let terms = self.terms.iter().map(|term| term.substitute(var, replacement)).collect();
+            let mut sum: Expr = SummationFunction::new(terms).into();
+            sum.collapse();
+            sum
         }
 
-        fn differentiate(&self) -> Box<dyn Entity> {
+        fn collapse(&mut self) {
+            for term in self.terms.iter_mut() {
+                term.collapse();
+            }
+            while fold_identities(&mut self.terms) {}
+
+            let mut i = 0;
+            while i < self.terms.len() {
+                let mut j = i + 1;
+                while j < self.terms.len() {
+                    match Self::try_add(&self.terms[i], &self.terms[j]) {
+                        Some(sum) => {
+                            self.terms[i] = sum;
+                            self.terms.remove(j);
+                        },
+                        None => j += 1,
+                    }
+                }
+                i += 1;
+            }
+
+            self.terms.retain(|term| !is_zero(term));
+
+            // quotients are brought over a common denominator, as in 1/x + 1/(x + 1) = (2x + 1)/(x^2 + x),
+            // unless one of them divides by zero
+            if self.terms.len() > 1 && self.terms.iter().any(|term| matches!(term, Expr::Quotient(_))) {
+                if let Some(mut combined) = together(&self.terms) {
+                    combined.collapse();
+                    self.terms = vec![combined];
+                }
+            }
+        }
+
+        fn reduce(&self) -> Option<Expr> {
+            match self.terms.len() {
+                0 => Some(create_number(0)),
+                1 => Some(self.terms[0].clone()),
+                _ => None,
+            }
+        }
+    }
+    impl Function for SummationFunction {
+
+    }
+
+    impl MultiplicationFunction {
+        fn differentiate_by(&self, d: &dyn Fn(&Expr) -> Expr) -> Expr {
             let mut scloned = self.clone();
             scloned.collapse();
 
-            let dfirst = scloned.first.differentiate();
-            let dsecond = scloned.second.differentiate();
+            let dfirst = d(&scloned.first);
+            let dsecond = d(&scloned.second);
+
+            let nfirst = MultiplicationFunction::new(scloned.first.clone(), dsecond).into();
+            let nsecond = MultiplicationFunction::new(scloned.second.clone(), dfirst).into();
 
-            let nfirst = Box::new(MultiplicationFunction::new(scloned.first.clone(), dsecond));
-            let nsecond = Box::new(MultiplicationFunction::new(scloned.second.clone(), dfirst));
-            
             let mut sum = SummationFunction::new(vec![nfirst, nsecond]);
             sum.collapse();
 
-            Box::new(sum)
+            sum.into()
         }
-        
-        fn get_kind(&self) -> EntityKind {
-            self.kind
+
+        // a*(n/d) as the quotient (a*n)/d
+        fn as_quotient(&self) -> Option<Expr> {
+            for (factor, other) in [(&self.first, &self.second), (&self.second, &self.first)] {
+                if let Expr::Quotient(quotient) = factor {
+                    let numerator = MultiplicationFunction::new(other.clone(), quotient.numerator.clone());
+                    let mut result: Expr = DivisionFunction::new(numerator.into(), quotient.denominator.clone()).into();
+                    result.collapse();
+                    return Some(result);
+                }
+            }
+            None
+        }
+
+        // a number times a product that has a numeric factor, folded into one number, e.g. 2*(3*pi) = 6*pi
+        fn fold_numbers(&self) -> Option<(Expr, Expr)> {
+            let (number, other) = match (as_number(&self.first), as_number(&self.second)) {
+                (Some(number), None) => (number, &self.second),
+                (None, Some(number)) => (number, &self.first),
+                _ => return None,
+            };
+
+            let Expr::Product(product) = other else {
+                return None;
+            };
+            let (inner, rest) = match (as_number(&product.first), as_number(&product.second)) {
+                (Some(inner), _) => (inner, &product.second),
+                (_, Some(inner)) => (inner, &product.first),
+                _ => return None,
+            };
+            Some((create_number(&number * &inner), rest.clone()))
+        }
+    }
+    impl ExprNode for MultiplicationFunction {
+        fn to_str(&self) -> String {
+            let mut str = String::new();
+
+            let s1 = parenthesize(&self.first);
+            let s2 = parenthesize(&self.second);
+
+            if s1 == "0" || s2 == "0" {
+                str += "0";
+            }
+            else if s1 == "1" {
+                str += &s2;
+            }
+            else if s2 == "1" {
+                str += &s1;
+            }
+            else if s1 == "-1" {
+                str += "-";
+                str += &s2;
+            }
+            else {
+                str += &s1;
+                str += "*";
+                str += &s2;
+            }
+
+            str
+        }
+
+        fn differentiate(&self) -> Expr {
+            self.differentiate_by(&|factor| factor.differentiate())
+        }
+
+        fn differentiate_wrt(&self, state: &DataState) -> Expr {
+            self.differentiate_by(&|factor| factor.differentiate_wrt(state))
+        }
+
+        fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            Ok(self.first.evaluate(env)? * self.second.evaluate(env)?)
         }
-        
-        fn as_any(&self) -> &dyn Any {
-            self
+
+        fn substitute(&self, var: &VariableIdentifier, replacement: &Expr) -> Expr {
+            collapsed_product(vec![self.first.substitute(var, replacement), self.second.substitute(var, replacement)])
         }
-        
+
         fn collapse(&mut self) {
             self.first.collapse();
             self.second.collapse();
 
-            if self.first.get_kind() == self.second.get_kind() {
-                let product: Box<dyn Entity> = match self.first.get_kind() {
-                    EntityKind::Constant => {
-                        let firstc = try_cast_to::<ConstantTerm>(&Box::new(self.first.as_any())).unwrap();
-                        let secondc = try_cast_to::<ConstantTerm>(&Box::new(self.second.as_any())).unwrap();
+            if let Some(quotient) = self.as_quotient() {
+                self.first = quotient;
+                self.second = create_number(1);
+                return;
+            }
 
-                        Box::new(firstc.multiply(secondc))
-                    },
-                    EntityKind::Variable => {
-                        let firstc = try_cast_to::<VariableTerm>(&Box::new(self.first.as_any())).unwrap();
-                        let secondc = try_cast_to::<VariableTerm>(&Box::new(self.second.as_any())).unwrap();
+            let product: Option<Expr> = match (&self.first, &self.second) {
+                (Expr::Constant(firstc), Expr::Constant(secondc)) => Some(firstc.multiply(secondc).into()),
+                (Expr::Variable(firstc), Expr::Variable(secondc)) => Some(firstc.multiply(secondc).into()),
+                (Expr::Variable(variable), constant @ Expr::Constant(_)) | (constant @ Expr::Constant(_), Expr::Variable(variable)) => {
+                    let mut term = (**variable).clone();
+                    term.coeffs.push(constant.clone());
+                    term.collapse();
+
+                    Some(term.into())
+                },
+                _ => None,
+            };
 
-                        Box::new(firstc.multiply(secondc))
-                    },
-                    EntityKind::Function => {
-                        Box::new(MultiplicationFunction::new(self.first.clone(), self.second.clone()))
-                    }
-                };
-                
-                println!("{}", product.to_str());
+            if let Some(product) = product {
                 self.first = product;
-                self.second = create_number(1.0);
+                self.second = create_number(1);
             }
-        
+            else if let Some((number, rest)) = self.fold_numbers() {
+                self.first = number;
+                self.second = rest;
+            }
+
             if self.first.to_str() == "0" || self.second.to_str() == "0" {
-                self.first = create_number(0.0);
-                self.second = create_number(0.0);
+                self.first = create_number(0);
+                self.second = create_number(0);
+            }
+        }
+
+        fn reduce(&self) -> Option<Expr> {
+            if is_zero(&self.first) {
+                Some(create_number(0))
+            }
+            else if self.second.to_str() == "1" {
+                Some(self.first.clone())
+            }
+            else if self.first.to_str() == "1" {
+                Some(self.second.clone())
+            }
+            else {
+                None
             }
         }
     }
@@ -660,5 +1120,600 @@ pub mod entity {
 
     }
 
+    impl DivisionFunction {
+        fn differentiate_by(&self, d: &dyn Fn(&Expr) -> Expr) -> Expr {
+            let mut scloned = self.clone();
+            scloned.collapse();
+
+            let dnumerator = d(&scloned.numerator);
+            let ddenominator = d(&scloned.denominator);
+
+            // (f'g - fg') / g^2
+            let first = MultiplicationFunction::new(dnumerator, scloned.denominator.clone()).into();
+            let second = MultiplicationFunction::new(scloned.numerator.clone(), ddenominator).into();
+            let numerator = SummationFunction::new(vec![first, MultiplicationFunction::new(create_number(-1), second).into()]);
+            let denominator = MultiplicationFunction::new(scloned.denominator.clone(), scloned.denominator.clone());
+
+            let mut quotient: Expr = DivisionFunction::new(numerator.into(), denominator.into()).into();
+            quotient.collapse();
+            quotient
+        }
+
+        // coefficient and variables of a term such as 3x^2*y
+        fn as_monomial(entity: &Expr) -> Option<(Rational, Vec<VariableEntity>)> {
+            match entity {
+                Expr::Constant(c) => Some((c.value.clone(), c.non_wrt_variables.clone())),
+                Expr::Variable(v) => {
+                    let mut v = (**v).clone();
+                    v.collapse();
+                    let [Expr::Constant(c)] = v.coeffs.as_slice() else {
+                        return None;
+                    };
+
+                    let mut vars = c.non_wrt_variables.clone();
+                    vars.push(v.variable.clone());
+                    Some((c.value.clone(), vars))
+                },
+                _ => None,
+            }
+        }
+
+        fn create_monomial(value: Rational, vars: Vec<VariableEntity>) -> Expr {
+            let mut constant = ConstantTerm::new(value, vars);
+            constant.collapse();
+            if constant.non_wrt_variables.is_empty() {
+                return constant.into();
+            }
+
+            let variable = constant.non_wrt_variables.remove(0);
+            VariableTerm::new(variable, vec![constant.into()]).into()
+        }
+
+        // variables, at their lowest positive power, that divide every term of `entity`
+        fn monomial_content(entity: &Expr) -> Option<Vec<VariableEntity>> {
+            if let Expr::Sum(sum) = entity {
+                let mut content: Option<Vec<VariableEntity>> = None;
+                for term in &sum.terms {
+                    let vars = Self::monomial_content(term)?;
+                    content = Some(match content {
+                        Some(content) => Self::common_variables(&content, &vars),
+                        None => vars,
+                    });
+                }
+                return content;
+            }
+
+            let (_, vars) = Self::as_monomial(entity)?;
+            let mut constant = ConstantTerm::new(Rational::one(), vars);
+            constant.collapse();
+            Some(constant.non_wrt_variables.into_iter().filter(|var| var.power.is_positive()).collect())
+        }
+
+        fn common_variables(first: &[VariableEntity], second: &[VariableEntity]) -> Vec<VariableEntity> {
+            let mut common = vec![];
+            for var in first {
+                if let Some(other) = second.iter().find(|other| other.variable == var.variable) {
+                    common.push(VariableEntity { variable: var.variable.clone(), power: (&var.power).min(&other.power).clone() });
+                }
+            }
+            common
+        }
+
+        fn divide_variables(entity: &Expr, divisor: &[VariableEntity]) -> Option<Expr> {
+            if let Expr::Sum(sum) = entity {
+                let terms = sum.terms.iter().map(|term| Self::divide_variables(term, divisor)).collect::<Option<_>>()?;
+                return Some(SummationFunction::new(terms).into());
+            }
+
+            let (value, mut vars) = Self::as_monomial(entity)?;
+            vars.extend(divisor.iter().map(|var| VariableEntity { variable: var.variable.clone(), power: -&var.power }));
+            Some(Self::create_monomial(value, vars))
+        }
+    }
+    impl ExprNode for DivisionFunction {
+        fn to_str(&self) -> String {
+            let numerator = parenthesize(&self.numerator);
+            let denominator = self.denominator.to_str();
+
+            if numerator == "0" && denominator != "0" {
+                "0".to_string()
+            }
+            else if denominator == "1" {
+                numerator
+            }
+            else if is_atomic(&denominator) {
+                format!("{}/{}", numerator, denominator)
+            }
+            else {
+                format!("{}/({})", numerator, denominator)
+            }
+        }
+
+        fn differentiate(&self) -> Expr {
+            self.differentiate_by(&|operand| operand.differentiate())
+        }
+
+        fn differentiate_wrt(&self, state: &DataState) -> Expr {
+            self.differentiate_by(&|operand| operand.differentiate_wrt(state))
+        }
+
+        fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            let numerator = self.numerator.evaluate(env)?;
+            let denominator = self.denominator.evaluate(env)?;
+            if denominator == 0.0 {
+                return Err(EvalError::DivisionByZero);
+            }
+            Ok(numerator / denominator)
+        }
+
+        fn substitute(&self, var: &VariableIdentifier, replacement: &Expr) -> Expr {
+            let numerator = self.numerator.substitute(var, replacement);
+            let denominator = self.denominator.substitute(var, replacement);
+            let mut quotient: Expr = DivisionFunction::new(numerator, denominator).into();
+            quotient.collapse();
+            quotient
+        }
+
+        fn collapse(&mut self) {
+            self.numerator.collapse();
+            self.denominator.collapse();
+
+            // 0/0 stays, for evaluate to report
+            if is_zero(&self.numerator) && !is_zero(&self.denominator) {
+                self.denominator = create_number(1);
+                return;
+            }
+
+            // monomial over monomial folds the coefficients and cancels powers
+            let numerator = Self::as_monomial(&self.numerator);
+            let denominator = Self::as_monomial(&self.denominator);
+            if let (Some((a, numerator_vars)), Some((b, denominator_vars))) = (numerator, denominator) {
+                if b.is_zero() {
+                    return;
+                }
+
+                let mut quotient = ConstantTerm::new(a / b, numerator_vars);
+                quotient.non_wrt_variables.extend(denominator_vars.into_iter().map(|var| VariableEntity { variable: var.variable, power: -&var.power }));
+                quotient.collapse();
+
+                let (positive, negative): (Vec<VariableEntity>, Vec<VariableEntity>) = quotient.non_wrt_variables.into_iter().partition(|var| var.power.is_positive());
+                let negative: Vec<VariableEntity> = negative.into_iter().map(|var| VariableEntity { variable: var.variable, power: -var.power }).collect();
+
+                // the coefficient's denominator moves below the line with the variables, as in 1/(3x)
+                let (numerator_value, denominator_value) = if negative.is_empty() {
+                    (quotient.value, Rational::one())
+                }
+                else {
+                    (quotient.value.numerator().clone().into(), quotient.value.denominator().clone().into())
+                };
+                self.numerator = Self::create_monomial(numerator_value, positive);
+                self.denominator = Self::create_monomial(denominator_value, negative);
+                return;
+            }
+
+            // cancel monomial factors shared by every term on both sides
+            if let (Some(numerator_content), Some(denominator_content)) = (Self::monomial_content(&self.numerator), Self::monomial_content(&self.denominator)) {
+                let common = Self::common_variables(&numerator_content, &denominator_content);
+                if !common.is_empty() {
+                    if let (Some(numerator), Some(denominator)) = (Self::divide_variables(&self.numerator, &common), Self::divide_variables(&self.denominator, &common)) {
+                        self.numerator = numerator;
+                        self.denominator = denominator;
+                        self.numerator.collapse();
+                        self.denominator.collapse();
+                    }
+                }
+            }
+
+            // polynomials lose their common factors, as in (x^2 - 1)/(x - 1) = x + 1
+            if let Some((numerator, denominator)) = cancel(&self.numerator, &self.denominator) {
+                self.numerator = numerator;
+                self.denominator = denominator;
+            }
+
+            // a constant denominator becomes a coefficient of the numerator
+            if let Some((b, vars)) = Self::as_monomial(&self.denominator) {
+                if vars.is_empty() && !b.is_zero() {
+                    self.numerator = MultiplicationFunction::new(create_number(b.recip()), self.numerator.clone()).into();
+                    self.denominator = create_number(1);
+                    self.numerator.collapse();
+                }
+            }
+        }
+
+        fn reduce(&self) -> Option<Expr> {
+            if is_zero(&self.numerator) && !is_zero(&self.denominator) {
+                Some(create_number(0))
+            }
+            else if self.denominator.to_str() == "1" {
+                Some(self.numerator.clone())
+            }
+            else {
+                None
+            }
+        }
+    }
+    impl Function for DivisionFunction {
+
+    }
+
+    impl PowerFunction {
+        fn differentiate_by(&self, d: &dyn Fn(&Expr) -> Expr) -> Expr {
+            let mut scloned = self.clone();
+            scloned.collapse();
+
+            let mut dbase = d(&scloned.base);
+            let mut dexponent = d(&scloned.exponent);
+            dbase.collapse();
+            dexponent.collapse();
+
+            let (base, exponent) = (scloned.base, scloned.exponent);
+            let mut derivative: Expr = if is_zero(&dexponent) {
+                // power rule: (u^c)' = c u^(c-1) u'
+                let lowered = SummationFunction::new(vec![exponent.clone(), create_number(-1)]).into();
+                let power = PowerFunction::new(base, lowered).into();
+                MultiplicationFunction::new(MultiplicationFunction::new(exponent, power).into(), dbase).into()
+            }
+            else if is_zero(&dbase) {
+                // (c^v)' = c^v ln(c) v'
+                let power = PowerFunction::new(base.clone(), exponent).into();
+                MultiplicationFunction::new(MultiplicationFunction::new(power, create_ln(base)).into(), dexponent).into()
+            }
+            else {
+                // (u^v)' = u^v (v' ln u + v u'/u)
+                let power = PowerFunction::new(base.clone(), exponent.clone()).into();
+                let logarithmic = MultiplicationFunction::new(dexponent, create_ln(base.clone())).into();
+                let ratio = DivisionFunction::new(MultiplicationFunction::new(exponent, dbase).into(), base).into();
+                MultiplicationFunction::new(power, SummationFunction::new(vec![logarithmic, ratio]).into()).into()
+            };
+
+            derivative.collapse();
+            derivative
+        }
+    }
+    impl ExprNode for PowerFunction {
+        fn to_str(&self) -> String {
+            let group = |entity: &Expr| {
+                let s = entity.to_str();
+                if is_atomic(&s) && !s.contains('^') { s } else { format!("({})", s) }
+            };
+            format!("{}^{}", group(&self.base), group(&self.exponent))
+        }
+
+        fn differentiate(&self) -> Expr {
+            self.differentiate_by(&|entity| entity.differentiate())
+        }
+
+        fn differentiate_wrt(&self, state: &DataState) -> Expr {
+            self.differentiate_by(&|entity| entity.differentiate_wrt(state))
+        }
+
+        fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            raise(self.base.evaluate(env)?, self.exponent.evaluate(env)?)
+        }
+
+        fn substitute(&self, var: &VariableIdentifier, replacement: &Expr) -> Expr {
+            let base = self.base.substitute(var, replacement);
+            let exponent = self.exponent.substitute(var, replacement);
+            let mut power: Expr = PowerFunction::new(base, exponent).into();
+            power.collapse();
+            power
+        }
+
+        fn collapse(&mut self) {
+            self.base.collapse();
+            self.exponent.collapse();
+        }
+
+        fn reduce(&self) -> Option<Expr> {
+            let exponent = as_number(&self.exponent);
+            if exponent == Some(Rational::zero()) || self.base.to_str() == "1" {
+                return Some(create_number(1));
+            }
+            if exponent == Some(Rational::one()) {
+                return Some(self.base.clone());
+            }
+
+            // half-integer powers of numbers are exact when the root is, e.g. 4^(3/2) = 8
+            if let (Some(base), Some(exponent)) = (as_number(&self.base), &exponent) {
+                let doubled = (exponent * &Rational::from(2)).to_i64().filter(|e| e.abs() <= 64 && e % 2 != 0);
+                if let Some(doubled) = doubled {
+                    if base.is_zero() {
+                        return None;
+                    }
+                    return base.pow(doubled as i32).sqrt().map(create_number);
+                }
+            }
+
+            // small integer powers of monomials are folded into the term arithmetic, e.g. (2x)^2 = 4x^2
+            let power = exponent?.to_i64().filter(|e| e.abs() <= 64)? as i32;
+            if !matches!(self.base, Expr::Constant(_) | Expr::Variable(_)) {
+                return None;
+            }
+
+            let mut product = self.base.clone();
+            for _ in 1..power.unsigned_abs() {
+                product = MultiplicationFunction::new(product, self.base.clone()).into();
+            }
+            if power < 0 {
+                product = DivisionFunction::new(create_number(1), product).into();
+            }
+
+            product.collapse();
+            Some(product)
+        }
+    }
+    impl Function for PowerFunction {
+
+    }
+
+    impl CompositionFunction {
+        fn differentiate_by(&self, d: &dyn Fn(&Expr) -> Expr) -> Expr {
+            let mut scloned = self.clone();
+            scloned.collapse();
+
+            // chain rule: f(u)' = f'(u) * u'
+            let outer = scloned.outer.derivative(&scloned.inner);
+            let inner = d(&scloned.inner);
+
+            let mut product: Expr = MultiplicationFunction::new(outer, inner).into();
+            product.collapse();
+            product
+        }
+    }
+    impl ExprNode for CompositionFunction {
+        fn to_str(&self) -> String {
+            format!("{}({})", self.outer.name(), self.inner.to_str())
+        }
+
+        fn differentiate(&self) -> Expr {
+            self.differentiate_by(&|inner| inner.differentiate())
+        }
+
+        fn differentiate_wrt(&self, state: &DataState) -> Expr {
+            self.differentiate_by(&|inner| inner.differentiate_wrt(state))
+        }
+
+        fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            let argument = self.inner.evaluate(env)?;
+            checked(&self.outer.name(), argument, self.outer.apply(argument))
+        }
+
+        fn substitute(&self, var: &VariableIdentifier, replacement: &Expr) -> Expr {
+            let mut composition: Expr = CompositionFunction::new(self.outer.clone(), self.inner.substitute(var, replacement)).into();
+            composition.collapse();
+            composition
+        }
+
+        fn collapse(&mut self) {
+            self.inner.collapse();
+        }
+
+        fn reduce(&self) -> Option<Expr> {
+            self.outer.simplify(&self.inner)
+        }
+    }
+    impl Function for CompositionFunction {
+
+    }
+
     ////// Differentiation //////
-}
\ No newline at end of file
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::derivatives::derivatives::DerivativeCache;
+        use crate::parser::parser::parse;
+
+        fn collapsed(input: &str) -> Expr {
+            let mut expr = parse(input).unwrap().to_expr();
+            expr.collapse();
+            expr
+        }
+
+        fn env(bindings: &[(&str, f64)]) -> HashMap<VariableIdentifier, f64> {
+            bindings.iter().map(|(name, value)| (VariableIdentifier::new(name), *value)).collect()
+        }
+
+        fn derivative(input: &str, var: &str) -> Expr {
+            collapsed(input).differentiate_wrt(&DataState::new(VariableIdentifier::new(var)))
+        }
+
+        // `actual` against the value of `expected` at a few points with x > 0
+        fn assert_agrees(actual: &Expr, expected: &str) {
+            let expected = parse(expected).unwrap().to_expr();
+            for point in [[("x", 0.7), ("y", 1.3), ("z", -2.0)], [("x", 1.9), ("y", -0.4), ("z", 0.5)], [("x", 3.2), ("y", 2.0), ("z", 1.0)]] {
+                let (value, expected_value) = (actual.evaluate(&env(&point)).unwrap(), expected.evaluate(&env(&point)).unwrap());
+                assert!((value - expected_value).abs() < 1e-9 * expected_value.abs().max(1.0), "{} at {:?}: {} != {}", actual.to_str(), point, value, expected_value);
+            }
+        }
+
+        #[test]
+        fn partial_derivatives_hold_the_other_variables_constant() {
+            let dy = derivative("x^2*y^3", "y");
+            assert_eq!(dy.to_str(), "3x^2*y^2");
+            assert_agrees(&dy, "3*x^2*y^2");
+            assert_agrees(&derivative("x^2*y^3", "x"), "2*x*y^3");
+            assert_agrees(&derivative("x*y + sin(y)*x^2", "y"), "x + x^2*cos(y)");
+
+            // variables other than the one differentiated by are constants
+            assert_eq!(derivative("x^2*y^3", "z").to_str(), "0");
+            assert_eq!(derivative("sin(x)*y + ln(x)/y", "z").to_str(), "0");
+        }
+
+        #[test]
+        fn quotients_follow_the_quotient_rule() {
+            let quotient = derivative("x/(x + 1)", "x");
+            assert_eq!(quotient.to_str(), "1/((x + 1)^2)");
+            assert_agrees(&quotient, "((x + 1) - x)/(x + 1)^2");
+            assert_agrees(&derivative("(x^2 + 1)/sin(x)", "x"), "(2*x*sin(x) - (x^2 + 1)*cos(x))/sin(x)^2");
+            assert_agrees(&derivative("y/x^2", "x"), "-2*y/x^3");
+        }
+
+        #[test]
+        fn quotients_of_monomials_cancel() {
+            for (input, expected) in [("x^3/x", "x^2"), ("6x^5/(2x^2)", "3x^3"), ("x/x^3", "1/x^2")] {
+                let quotient = collapsed(input);
+                assert_eq!(quotient.to_str(), expected);
+                assert_agrees(&quotient, input);
+            }
+        }
+
+        #[test]
+        fn zero_over_zero_stays_undefined() {
+            let at_two = env(&[("x", 2.0), ("y", 3.0)]);
+            for input in ["0/0", "(x - x)/(y - y)", "0/(y - y)"] {
+                let quotient = collapsed(input);
+                assert_eq!(quotient.to_str(), "0/0");
+                assert_eq!(quotient.evaluate(&at_two), Err(EvalError::DivisionByZero));
+            }
+            assert_eq!(collapsed("(x - x)/y").to_str(), "0");
+
+            // the quotient rule over a zero denominator gives 0/0, not 0
+            assert_eq!(derivative("(x + 1)/(y - y)", "x").evaluate(&at_two), Err(EvalError::DivisionByZero));
+        }
+
+        #[test]
+        fn compositions_follow_the_chain_rule() {
+            let composition = derivative("sin(x^2)", "x");
+            assert_eq!(composition.to_str(), "cos(x^2)*2x");
+            assert_agrees(&composition, "2*x*cos(x^2)");
+
+            // nested compositions take the derivative of every layer
+            assert_agrees(&derivative("ln(cos(x^2))", "x"), "-2*x*sin(x^2)/cos(x^2)");
+            assert_agrees(&derivative("exp(sin(2x))", "x"), "2*cos(2*x)*exp(sin(2*x))");
+            assert_agrees(&derivative("sin(x*y)", "y"), "x*cos(x*y)");
+        }
+
+        #[test]
+        fn powers_follow_the_power_and_exponential_rules() {
+            // a constant exponent
+            assert_eq!(derivative("x^(5/2)", "x").to_str(), "5/2*x^(3/2)");
+            assert_agrees(&derivative("sin(x)^3", "x"), "3*sin(x)^2*cos(x)");
+
+            // a constant base
+            let exponential = derivative("2^x", "x");
+            assert_eq!(exponential.to_str(), "2^x*ln(2)");
+            assert_agrees(&exponential, "2^x*ln(2)");
+
+            // both vary
+            assert_agrees(&derivative("x^x", "x"), "x^x*(ln(x) + 1)");
+            assert_agrees(&derivative("x^y", "x"), "y*x^(y - 1)");
+            assert_agrees(&derivative("x^y", "y"), "x^y*ln(x)");
+        }
+
+        #[test]
+        fn equal_expressions_hash_and_order_alike() {
+            use std::{cmp::Ordering, collections::HashSet, hash::{DefaultHasher, Hash, Hasher}};
+
+            let hash = |expr: &Expr| {
+                let mut hasher = DefaultHasher::new();
+                expr.hash(&mut hasher);
+                hasher.finish()
+            };
+
+            // parsed twice, so that no node is shared between them
+            let inputs = ["x^2*y + sin(x)", "1/(x + 1)", "x^(1/2)", "ln(x)*exp(y)"];
+            let first: Vec<Expr> = inputs.iter().map(|input| collapsed(input)).collect();
+            let second: Vec<Expr> = inputs.iter().map(|input| collapsed(input)).collect();
+            for (a, b) in first.iter().zip(&second) {
+                assert_eq!(a, b);
+                assert_eq!(a.cmp(b), Ordering::Equal);
+                assert_eq!(hash(a), hash(b));
+            }
+            for a in &first {
+                for b in &second {
+                    assert_eq!(a == b, a.cmp(b) == Ordering::Equal, "{} and {}", a.to_str(), b.to_str());
+                    assert_eq!(a.cmp(b), b.cmp(a).reverse());
+                }
+            }
+
+            let set: HashSet<Expr> = first.iter().chain(&second).cloned().collect();
+            assert_eq!(set.len(), inputs.len());
+
+            let names: HashMap<Expr, &str> = first.iter().cloned().zip(inputs).collect();
+            for (key, input) in second.iter().zip(inputs) {
+                assert_eq!(names.get(key), Some(&input));
+            }
+            assert_eq!(names.get(&collapsed("x^2*y + cos(x)")), None);
+        }
+
+        #[test]
+        fn evaluation_binds_every_variable() {
+            let expr = parse("3x^2*y + ln(x)/y").unwrap().to_expr();
+            let value = expr.evaluate(&env(&[("x", 1.0), ("y", 2.0)])).unwrap();
+            assert_eq!(value, 6.0);
+            assert_eq!(expr.evaluate(&env(&[("x", 1.0)])), Err(EvalError::UnboundVariable(VariableIdentifier::new("y"))));
+
+            // variables riding along as non-wrt variables of a constant are bound too
+            let constant: Expr = ConstantTerm::new(Rational::from(3), vec![VariableEntity::new(VariableIdentifier::new("y"), Rational::from(2))]).into();
+            assert_eq!(constant.evaluate(&env(&[("y", 2.0)])), Ok(12.0));
+            assert_eq!(constant.evaluate(&env(&[("x", 2.0)])), Err(EvalError::UnboundVariable(VariableIdentifier::new("y"))));
+        }
+
+        #[test]
+        fn evaluation_reports_division_by_zero_and_domain_errors() {
+            let at_zero = env(&[("x", 0.0)]);
+            assert_eq!(parse("1/x").unwrap().evaluate(&at_zero), Err(EvalError::DivisionByZero));
+            assert_eq!(parse("x^-2").unwrap().evaluate(&at_zero), Err(EvalError::DivisionByZero));
+            assert_eq!(parse("(x - 1)^(1/2)").unwrap().evaluate(&at_zero), Err(EvalError::Domain { function: "^".to_string(), argument: -1.0 }));
+            assert_eq!(parse("ln(x)").unwrap().evaluate(&at_zero), Err(EvalError::Domain { function: "ln".to_string(), argument: 0.0 }));
+            assert_eq!(EvalError::DivisionByZero.to_string(), "division by zero");
+        }
+
+        #[test]
+        fn substitution_reaches_non_wrt_variables_and_coeffs() {
+            let y = VariableIdentifier::new("y");
+            let replacement = parse("x + 1").unwrap().to_expr();
+            let at = env(&[("x", 2.0)]);
+
+            // 3y^2, with y held as a non-wrt variable of the constant
+            let constant: Expr = ConstantTerm::new(Rational::from(3), vec![VariableEntity::new(y.clone(), Rational::from(2))]).into();
+            assert_eq!(constant.substitute(&y, &replacement).evaluate(&at), Ok(27.0));
+            assert_eq!(constant.substitute(&y, &create_number(2)).to_str(), "12");
+
+            // 3x^2*y, with y among the coeffs of the x term
+            let term: Expr = VariableTerm::new(VariableEntity::new(VariableIdentifier::new("x"), Rational::from(2)), vec![create_variable("y", 1), create_number(3)]).into();
+            assert_eq!(term.substitute(&y, &replacement).evaluate(&at), Ok(36.0));
+            assert_eq!(term.substitute(&y, &create_number(2)).to_str(), "6x^2");
+        }
+
+        #[test]
+        fn substitution_collapses_the_result() {
+            let (x, y) = (VariableIdentifier::new("x"), VariableIdentifier::new("y"));
+            assert_eq!(parse("x*y").unwrap().to_expr().substitute(&x, &create_number(0)).to_str(), "0");
+            assert_eq!(parse("x^2 + y").unwrap().to_expr().substitute(&x, &parse("y").unwrap().to_expr()).to_str(), "y^2 + y");
+
+            let substituted = parse("sin(y)/y + y^2").unwrap().to_expr().substitute(&y, &parse("2x").unwrap().to_expr());
+            let value = substituted.evaluate(&env(&[("x", 0.5)])).unwrap();
+            assert!((value - (1f64.sin() + 1.0)).abs() < 1e-12, "{}", substituted.to_str());
+        }
+
+        #[test]
+        fn quotients_of_fractional_powers_print_what_they_parse_to() {
+            let x = VariableIdentifier::new("x");
+            let env = HashMap::from([(x.clone(), 1.7), (VariableIdentifier::new("y"), 0.6)]);
+            let mut cache = DerivativeCache::new();
+
+            for input in ["x^(3/2)", "x^(2/3)*y^(1/3)", "x^(-1/2)*y^2", "1/(2*sin(x))"] {
+                for derivative in cache.derivatives(&parse(input).unwrap().to_expr(), &x, 2) {
+                    let printed = derivative.to_str();
+                    let reparsed = parse(&printed).unwrap().evaluate(&env).unwrap();
+                    let value = derivative.evaluate(&env).unwrap();
+                    assert!((reparsed - value).abs() < 1e-12 * value.abs(), "{}: {} != {}", printed, reparsed, value);
+                }
+            }
+            assert_eq!(cache.nth_derivative(&parse("x^(3/2)").unwrap().to_expr(), &x, 2).to_str(), "3/(4x^(1/2))");
+        }
+
+        #[test]
+        fn sums_over_a_zero_denominator_stay_apart() {
+            let env = HashMap::from([(VariableIdentifier::new("x"), 2.0)]);
+            for input in ["1/(x-x) + 1/x", "1/(sin(x)-sin(x)) + 1"] {
+                let expr = collapsed(input);
+                assert!(matches!(&expr, Expr::Sum(sum) if sum.terms().len() == 2), "{}", expr.to_str());
+                assert_eq!(expr.evaluate(&env), Err(EvalError::DivisionByZero));
+            }
+        }
+    }
+}
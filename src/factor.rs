@@ -0,0 +1,534 @@
+pub mod factor {
+    use std::cmp::Ordering;
+    use crate::bigint::bigint::BigInt;
+    use crate::rational::rational::Rational;
+    use crate::entity::entity::{create_number, Expr, MultiplicationFunction, PowerFunction};
+    use crate::polynomial::polynomial::{degree, derivative, divide, gcd, leading, polynomial_expr, primitive, subtract, trimmed, univariate, Coefficients};
+
+    //---- Factorization ----//
+    // Yun's algorithm: the square-free parts of `polynomial` with their multiplicities
+    fn square_free(polynomial: &Coefficients) -> Vec<(Coefficients, u32)> {
+        let mut parts = vec![];
+        let common = gcd(polynomial, &derivative(polynomial));
+        let mut b = divide(polynomial, &common).0;
+        let mut d = subtract(&divide(&derivative(polynomial), &common).0, &derivative(&b));
+
+        let mut multiplicity = 1;
+        while degree(&b) > 0 {
+            let a = gcd(&b, &d);
+            let c = divide(&d, &a).0;
+            b = divide(&b, &a).0;
+            d = subtract(&c, &derivative(&b));
+            if degree(&a) > 0 {
+                parts.push((a, multiplicity));
+            }
+            multiplicity += 1;
+        }
+        parts
+    }
+
+    // the irreducible factors of a primitive square-free polynomial
+    fn irreducible_factors(polynomial: Coefficients) -> Vec<Coefficients> {
+        if degree(&polynomial) <= 1 {
+            return if degree(&polynomial) == 1 { vec![polynomial] } else { vec![] };
+        }
+
+        let (p, modular) = modular_factors(&polynomial);
+        if modular.len() == 1 {
+            return vec![polynomial];
+        }
+        let lifted = lifted(&polynomial, p, &modular);
+        recombined(polynomial, &lifted)
+    }
+    ////// Factorization //////
+
+
+    //---- Modular Factorization ----//
+    // Polynomials over the integers modulo an odd prime p < 2^31, coefficients from the constant
+    // term up, without trailing zeros
+    type Modular = Vec<u64>;
+
+    fn modular_trimmed(mut polynomial: Modular) -> Modular {
+        while polynomial.last() == Some(&0) {
+            polynomial.pop();
+        }
+        polynomial
+    }
+
+    fn modular(polynomial: &Coefficients, p: u64) -> Modular {
+        let modulus = BigInt::from(p as i64);
+        modular_trimmed(polynomial.iter().map(|coefficient| residue(coefficient.numerator(), &modulus).to_i64().unwrap() as u64).collect())
+    }
+
+    fn modular_subtract(first: &Modular, second: &Modular, p: u64) -> Modular {
+        let difference = (0..first.len().max(second.len()))
+            .map(|i| (first.get(i).unwrap_or(&0) + p - second.get(i).unwrap_or(&0)) % p)
+            .collect();
+        modular_trimmed(difference)
+    }
+
+    fn modular_multiply(first: &Modular, second: &Modular, p: u64) -> Modular {
+        if first.is_empty() || second.is_empty() {
+            return vec![];
+        }
+
+        let mut product = vec![0; first.len() + second.len() - 1];
+        for (i, a) in first.iter().enumerate() {
+            for (j, b) in second.iter().enumerate() {
+                product[i + j] = (product[i + j] + a * b) % p;
+            }
+        }
+        modular_trimmed(product)
+    }
+
+    fn modular_scale(polynomial: &Modular, factor: u64, p: u64) -> Modular {
+        modular_trimmed(polynomial.iter().map(|coefficient| coefficient * factor % p).collect())
+    }
+
+    fn modular_divide(dividend: &Modular, divisor: &Modular, p: u64) -> (Modular, Modular) {
+        let mut remainder = dividend.clone();
+        if divisor.len() > dividend.len() {
+            return (vec![], remainder);
+        }
+
+        let mut quotient = vec![0; dividend.len() - divisor.len() + 1];
+        let inverse = inverse(*divisor.last().unwrap(), p);
+        while remainder.len() >= divisor.len() {
+            let shift = remainder.len() - divisor.len();
+            let coefficient = remainder.last().unwrap() * inverse % p;
+            for (i, term) in divisor.iter().enumerate() {
+                remainder[shift + i] = (remainder[shift + i] + p - term * coefficient % p) % p;
+            }
+            quotient[shift] = coefficient;
+            remainder = modular_trimmed(remainder);
+        }
+        (modular_trimmed(quotient), remainder)
+    }
+
+    fn modular_remainder(dividend: &Modular, divisor: &Modular, p: u64) -> Modular {
+        modular_divide(dividend, divisor, p).1
+    }
+
+    fn monic(polynomial: &Modular, p: u64) -> Modular {
+        match polynomial.last() {
+            Some(&lead) => modular_scale(polynomial, inverse(lead, p), p),
+            None => vec![],
+        }
+    }
+
+    // the monic greatest common divisor
+    fn modular_gcd(first: &Modular, second: &Modular, p: u64) -> Modular {
+        let (mut a, mut b) = (first.clone(), second.clone());
+        while !b.is_empty() {
+            let remainder = modular_remainder(&a, &b, p);
+            a = b;
+            b = remainder;
+        }
+        monic(&a, p)
+    }
+
+    fn modular_derivative(polynomial: &Modular, p: u64) -> Modular {
+        modular_trimmed(polynomial.iter().enumerate().skip(1).map(|(i, coefficient)| coefficient * (i as u64 % p) % p).collect())
+    }
+
+    // base^exponent reduced modulo `modulus`
+    fn power_mod(base: &Modular, mut exponent: u64, modulus: &Modular, p: u64) -> Modular {
+        let mut result = vec![1];
+        let mut base = modular_remainder(base, modulus, p);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = modular_remainder(&modular_multiply(&result, &base, p), modulus, p);
+            }
+            base = modular_remainder(&modular_multiply(&base, &base, p), modulus, p);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    // a^-1 modulo `modulus`, for `a` coprime to it
+    fn inverse_mod(a: &Modular, modulus: &Modular, p: u64) -> Modular {
+        // the extended Euclidean algorithm, keeping only the coefficients of `a`
+        let (mut r0, mut r1) = (modulus.clone(), modular_remainder(a, modulus, p));
+        let (mut s0, mut s1): (Modular, Modular) = (vec![], vec![1]);
+        while !r1.is_empty() {
+            let (quotient, remainder) = modular_divide(&r0, &r1, p);
+            let s = modular_subtract(&s0, &modular_multiply(&quotient, &s1, p), p);
+            (r0, r1) = (r1, remainder);
+            (s0, s1) = (s1, s);
+        }
+        modular_scale(&s0, inverse(r0[0], p), p)
+    }
+
+    fn inverse(a: u64, p: u64) -> u64 {
+        let (mut result, mut base, mut exponent) = (1, a % p, p - 2);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base % p;
+            }
+            base = base * base % p;
+            exponent >>= 1;
+        }
+        result
+    }
+
+    // The distinct-degree factorization of a monic square-free polynomial: the products of its
+    // irreducible factors of each degree, with that degree
+    fn distinct_degree(polynomial: &Modular, p: u64) -> Vec<(Modular, usize)> {
+        let x = vec![0, 1];
+        let mut parts = vec![];
+        let mut remaining = polynomial.clone();
+        let mut power = x.clone();
+
+        let mut d = 1;
+        while remaining.len() > 2 * d {
+            // x^(p^d) - x is the product of every monic irreducible polynomial of a degree dividing d
+            power = power_mod(&power, p, &remaining, p);
+            let part = modular_gcd(&modular_subtract(&power, &x, p), &remaining, p);
+            if part.len() > 1 {
+                remaining = modular_divide(&remaining, &part, p).0;
+                power = modular_remainder(&power, &remaining, p);
+                parts.push((part, d));
+            }
+            d += 1;
+        }
+        if remaining.len() > 1 {
+            let d = remaining.len() - 1;
+            parts.push((remaining, d));
+        }
+        parts
+    }
+
+    // Cantor and Zassenhaus' method: the monic irreducible factors of a product of ones of degree d.
+    // For a random a, a^((p^d - 1)/2) is 1 modulo some of the factors and -1 or 0 modulo the others
+    fn equal_degree(polynomial: &Modular, d: usize, p: u64, seed: &mut u64) -> Vec<Modular> {
+        if polynomial.len() - 1 == d {
+            return vec![polynomial.clone()];
+        }
+
+        loop {
+            let a: Modular = modular_trimmed((0..polynomial.len() - 1).map(|_| next_random(seed) % p).collect());
+            if a.len() < 2 {
+                continue;
+            }
+
+            // (p^d - 1)/2 = (1 + p + ... + p^(d - 1)) (p - 1)/2, through powers of the Frobenius map
+            let mut frobenius = a.clone();
+            let mut norm = a.clone();
+            for _ in 1..d {
+                frobenius = power_mod(&frobenius, p, polynomial, p);
+                norm = modular_remainder(&modular_multiply(&norm, &frobenius, p), polynomial, p);
+            }
+            let half = power_mod(&norm, (p - 1) / 2, polynomial, p);
+
+            let split = modular_gcd(&modular_subtract(&half, &vec![1], p), polynomial, p);
+            if split.len() > 1 && split.len() < polynomial.len() {
+                let rest = modular_divide(polynomial, &split, p).0;
+                let mut factors = equal_degree(&split, d, p, seed);
+                factors.extend(equal_degree(&rest, d, p, seed));
+                return factors;
+            }
+        }
+    }
+
+    // a fixed xorshift sequence, so that factoring the same polynomial always takes the same steps
+    fn next_random(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    fn is_prime(n: u64) -> bool {
+        n >= 2 && (2..).take_while(|i| i * i <= n).all(|i| !n.is_multiple_of(i))
+    }
+
+    // How many of the first primes that keep the polynomial square-free are compared, since the
+    // fewer factors it has modulo p, the fewer combinations of them are tried
+    const PRIMES_COMPARED: usize = 5;
+
+    // A prime that keeps `polynomial` square-free with its degree, and the monic irreducible factors
+    // of the polynomial modulo that prime, for the prime that leaves the fewest
+    fn modular_factors(polynomial: &Coefficients) -> (u64, Vec<Modular>) {
+        let mut best: Option<(u64, Vec<(Modular, usize)>)> = None;
+        let mut compared = 0;
+        for p in (3..).step_by(2).filter(|&p| is_prime(p)) {
+            let reduced = modular(polynomial, p);
+            if reduced.len() != polynomial.len() || modular_gcd(&reduced, &modular_derivative(&reduced, p), p).len() > 1 {
+                continue;
+            }
+
+            let parts = distinct_degree(&monic(&reduced, p), p);
+            let count = |parts: &[(Modular, usize)]| parts.iter().map(|(part, d)| (part.len() - 1) / d).sum::<usize>();
+            if best.as_ref().is_none_or(|(_, known)| count(&parts) < count(known)) {
+                best = Some((p, parts));
+            }
+
+            compared += 1;
+            if compared == PRIMES_COMPARED || best.as_ref().is_some_and(|(_, known)| count(known) == 1) {
+                break;
+            }
+        }
+
+        let (p, parts) = best.unwrap();
+        let mut seed = 0x2545_f491_4f6c_dd1d;
+        let factors = parts.iter().flat_map(|(part, d)| equal_degree(part, *d, p, &mut seed)).collect();
+        (p, factors)
+    }
+    ////// Modular Factorization //////
+
+
+    //---- Hensel Lifting ----//
+    // coefficients modulo some power of p, from the constant term up
+    type Lifted = Vec<BigInt>;
+
+    // `n` modulo `modulus`, between 0 and the modulus
+    fn residue(n: &BigInt, modulus: &BigInt) -> BigInt {
+        let remainder = n % modulus;
+        if remainder.is_negative() { &remainder + modulus } else { remainder }
+    }
+
+    // a^-1 modulo `modulus`, for `a` coprime to it
+    fn inverse_residue(a: &BigInt, modulus: &BigInt) -> BigInt {
+        let (mut r0, mut r1) = (modulus.clone(), residue(a, modulus));
+        let (mut s0, mut s1) = (BigInt::zero(), BigInt::one());
+        while !r1.is_zero() {
+            let (quotient, remainder) = r0.div_rem(&r1);
+            let s = &s0 - &(&quotient * &s1);
+            (r0, r1) = (r1, remainder);
+            (s0, s1) = (s1, s);
+        }
+        residue(&s0, modulus)
+    }
+
+    fn lifted_multiply(first: &Lifted, second: &Lifted, modulus: &BigInt) -> Lifted {
+        let mut product = vec![BigInt::zero(); first.len() + second.len() - 1];
+        for (i, a) in first.iter().enumerate() {
+            for (j, b) in second.iter().enumerate() {
+                product[i + j] = &product[i + j] + &(a * b);
+            }
+        }
+        product.iter().map(|coefficient| residue(coefficient, modulus)).collect()
+    }
+
+    // The bound on the coefficients of the factors of `polynomial` times its leading coefficient,
+    // from Mignotte's bound 2^n |f|, with the 2-norm |f| no more than n + 1 times the largest coefficient
+    fn coefficient_bound(polynomial: &Coefficients) -> BigInt {
+        let largest = polynomial.iter().map(|coefficient| coefficient.numerator().abs()).max().unwrap();
+        let n = degree(polynomial);
+        &(&BigInt::one().shifted_left(n as u64) * &BigInt::from((n + 1) as i64)) * &(&largest * &leading(polynomial).numerator().abs())
+    }
+
+    // Hensel's lemma: the monic factors of `polynomial` modulo p, lifted to factors modulo a power
+    // of p more than twice the bound on the coefficients, one power of p at a time
+    fn lifted(polynomial: &Coefficients, p: u64, factors: &[Modular]) -> (BigInt, Vec<Lifted>) {
+        let prime = BigInt::from(p as i64);
+        let bound = &coefficient_bound(polynomial) * &BigInt::from(2);
+        let mut modulus = prime.clone();
+        let mut powers = 1;
+        while modulus <= bound {
+            modulus = &modulus * &prime;
+            powers += 1;
+        }
+
+        // the polynomial made monic modulo the final power, which it is congruent to the product of the factors modulo
+        let lead = inverse_residue(leading(polynomial).numerator(), &modulus);
+        let target: Lifted = polynomial.iter().map(|coefficient| residue(&(coefficient.numerator() * &lead), &modulus)).collect();
+
+        // s_i with the sum of s_i times the product of the other factors equal to 1 modulo p
+        let cofactors: Vec<Modular> = (0..factors.len())
+            .map(|i| {
+                let others = factors.iter().enumerate().filter(|(j, _)| *j != i).fold(vec![1], |product, (_, factor)| modular_multiply(&product, factor, p));
+                inverse_mod(&others, &factors[i], p)
+            })
+            .collect();
+
+        let mut lifted: Vec<Lifted> = factors.iter().map(|factor| factor.iter().map(|&coefficient| BigInt::from(coefficient as i64)).collect()).collect();
+        let mut power = prime.clone();
+        for _ in 1..powers {
+            let next = &power * &prime;
+
+            // the error is divisible by the current power, and its next digit in base p is corrected
+            let product = lifted.iter().skip(1).fold(lifted[0].clone(), |product, factor| lifted_multiply(&product, factor, &next));
+            let error: Lifted = target.iter().zip(&product).map(|(a, b)| &residue(&(a - b), &next) / &power).collect();
+            let error: Modular = modular_trimmed(error.iter().map(|digit| digit.to_i64().unwrap() as u64 % p).collect());
+
+            // the correction of each factor has a lower degree than the factor, which stays monic
+            for ((factor, base), cofactor) in lifted.iter_mut().zip(factors).zip(&cofactors) {
+                let correction = modular_remainder(&modular_multiply(cofactor, &error, p), base, p);
+                for (coefficient, digit) in factor.iter_mut().zip(correction) {
+                    *coefficient = &*coefficient + &(&power * &BigInt::from(digit as i64));
+                }
+            }
+            power = next;
+        }
+        (modulus, lifted)
+    }
+    ////// Hensel Lifting //////
+
+
+    //---- Recombination ----//
+    // the integer polynomial congruent to `polynomial`, with coefficients between -modulus/2 and modulus/2
+    fn symmetric(polynomial: &Lifted, modulus: &BigInt) -> Coefficients {
+        let half = modulus / &BigInt::from(2);
+        trimmed(polynomial.iter().map(|coefficient| Rational::from(if *coefficient > half { coefficient - modulus } else { coefficient.clone() })).collect())
+    }
+
+    // the next combination of `size` indices below `count` in lexicographic order
+    fn next_combination(indices: &mut [usize], count: usize) -> bool {
+        let size = indices.len();
+        let Some(position) = (0..size).rev().find(|&i| indices[i] < count - size + i) else {
+            return false;
+        };
+        indices[position] += 1;
+        for i in position + 1..size {
+            indices[i] = indices[i - 1] + 1;
+        }
+        true
+    }
+
+    // Zassenhaus' recombination: every true factor is the leading coefficient times a product of
+    // lifted factors, reduced to symmetric coefficients, so products of more and more of them are tried
+    fn recombined(polynomial: Coefficients, (modulus, lifted): &(BigInt, Vec<Lifted>)) -> Vec<Coefficients> {
+        let mut factors = vec![];
+        let mut remaining = polynomial;
+        let mut left: Vec<&Lifted> = lifted.iter().collect();
+
+        let mut size = 1;
+        while 2 * size <= left.len() {
+            let mut indices: Vec<usize> = (0..size).collect();
+            let found = loop {
+                let lead = Rational::from(leading(&remaining).numerator().clone());
+                let product = indices.iter().fold(vec![residue(lead.numerator(), modulus)], |product, &i| lifted_multiply(&product, left[i], modulus));
+                let candidate = primitive(&symmetric(&product, modulus)).1;
+
+                let (quotient, rest) = divide(&remaining, &candidate);
+                if rest.is_empty() {
+                    factors.push(candidate);
+                    remaining = primitive(&quotient).1;
+                    break Some(indices);
+                }
+                if !next_combination(&mut indices, left.len()) {
+                    break None;
+                }
+            };
+
+            match found {
+                Some(indices) => {
+                    for i in indices.into_iter().rev() {
+                        left.remove(i);
+                    }
+                },
+                None => size += 1,
+            }
+        }
+
+        // what no smaller combination divides is irreducible
+        if degree(&remaining) > 0 {
+            factors.push(remaining);
+        }
+        factors
+    }
+    ////// Recombination //////
+
+
+    //---- Expressions ----//
+    // lower degrees first, with a bare power of the variable before the other factors of its degree
+    fn factor_order(first: &Coefficients, second: &Coefficients) -> Ordering {
+        let is_monomial = |polynomial: &Coefficients| polynomial.iter().filter(|coefficient| !coefficient.is_zero()).count() == 1;
+        first.len().cmp(&second.len())
+            .then_with(|| is_monomial(second).cmp(&is_monomial(first)))
+            .then_with(|| first.cmp(second))
+    }
+
+    // The factorization over the rationals of a polynomial in one variable, as its content times
+    // powers of irreducible primitive factors. Anything else is returned collapsed
+    pub fn factor(expr: &Expr) -> Expr {
+        let mut collapsed = expr.clone();
+        collapsed.collapse();
+
+        let Some((Some(variable), polynomial)) = univariate(&collapsed) else {
+            return collapsed;
+        };
+        if degree(&polynomial) == 0 {
+            return create_number(leading(&polynomial));
+        }
+
+        let mut factors: Vec<(Coefficients, u32)> = vec![];
+        for (part, multiplicity) in square_free(&polynomial) {
+            for factor in irreducible_factors(primitive(&part).1) {
+                factors.push((primitive(&factor).1, multiplicity));
+            }
+        }
+        factors.sort_by(|(first, _), (second, _)| factor_order(first, second));
+
+        // whatever the primitive factors leave of the leading coefficient is the content
+        let leads = factors.iter().fold(Rational::one(), |product, (factor, multiplicity)| &product * &leading(factor).pow(*multiplicity as i32));
+        let content = &leading(&polynomial) / &leads;
+
+        let mut product: Vec<Expr> = factors
+            .iter()
+            .map(|(factor, multiplicity)| {
+                let factor = polynomial_expr(&variable, factor);
+                if *multiplicity == 1 { factor } else { PowerFunction::new(factor, create_number(*multiplicity as i64)).into() }
+            })
+            .collect();
+        if content != Rational::one() {
+            product.insert(0, create_number(content));
+        }
+        product.into_iter().reduce(|product, factor| MultiplicationFunction::new(product, factor).into()).unwrap()
+    }
+    ////// Expressions //////
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::parser::parser::parse;
+
+        fn factored(input: &str) -> String {
+            factor(&parse(input).unwrap().to_expr()).to_str()
+        }
+
+        #[test]
+        fn content_and_repeated_factors() {
+            assert_eq!(factored("3*x^2 - 3"), "3*(x - 1)*(x + 1)");
+            assert_eq!(factored("(x + 1)^3*(x - 2)^2"), "(x - 2)^2*(x + 1)^3");
+            assert_eq!(factored("x^3 - x"), "x*(x - 1)*(x + 1)");
+            assert_eq!(factored("x^8 - 1/4"), "1/4*(2x^4 - 1)*(2x^4 + 1)");
+        }
+
+        #[test]
+        fn irreducible_polynomials_stay_whole() {
+            assert_eq!(factored("x^2 + 1"), "x^2 + 1");
+            assert_eq!(factored("x^2 - 2"), "x^2 - 2");
+            // x^4 + 1 splits modulo every prime
+            assert_eq!(factored("x^4 + 1"), "x^4 + 1");
+            assert_eq!(factored("6*x^4 - 5*x^3 + 11*x^2 - 10*x - 10"), "6x^4 - 5x^3 + 11x^2 - 10x - 10");
+        }
+
+        #[test]
+        fn factors_without_rational_roots_split_off() {
+            assert_eq!(factored("x^12 + 4"), "(x^6 - 2x^3 + 2)*(x^6 + 2x^3 + 2)");
+            assert_eq!(
+                factored("x^30 - 1"),
+                "(x - 1)*(x + 1)*(x^2 - x + 1)*(x^2 + x + 1)*(x^4 - x^3 + x^2 - x + 1)*(x^4 + x^3 + x^2 + x + 1)*(x^8 - x^7 + x^5 - x^4 + x^3 - x + 1)*(x^8 + x^7 - x^5 - x^4 - x^3 + x + 1)"
+            );
+            assert_eq!(
+                factored("x^40 - 1"),
+                "(x - 1)*(x + 1)*(x^2 + 1)*(x^4 - x^3 + x^2 - x + 1)*(x^4 + 1)*(x^4 + x^3 + x^2 + x + 1)*(x^8 - x^6 + x^4 - x^2 + 1)*(x^16 - x^12 + x^8 - x^4 + 1)"
+            );
+        }
+
+        #[test]
+        fn linear_factors_split_off_beside_huge_coefficients() {
+            assert_eq!(factored("(x - 1)*(x^3 + 10000000000001)"), "(x - 1)*(x^3 + 10000000000001)");
+            assert_eq!(factored("(x - 1)*(10000000000000*x^3 + 1)"), "(x - 1)*(10000000000000x^3 + 1)");
+            assert_eq!(factored("(2*x + 1)*(x^4 + 10000000000001)"), "(2x + 1)*(x^4 + 10000000000001)");
+        }
+
+        #[test]
+        fn huge_irreducible_cofactors_stay_whole() {
+            assert_eq!(factored("(x - 1)*(10000000000000*x^3 + 10000000000000*x^2 + 1)"), "(x - 1)*(10000000000000x^3 + 10000000000000x^2 + 1)");
+        }
+    }
+}
@@ -1,9 +1,19 @@
-use entity::entity::{create_number, create_variable, Entity, MultiplicationFunction, SummationFunction, VariableTerm};
+#![allow(clippy::module_inception)]
 
+use entity::entity::{create_variable, Expr, MultiplicationFunction};
+
+pub mod bigint;
+pub mod canonical;
+pub mod derivatives;
+pub mod elementary;
 pub mod entity;
+pub mod factor;
+pub mod parser;
+pub mod polynomial;
+pub mod rational;
 
 fn main() {
-    let f = MultiplicationFunction::new(create_variable("x", 3), create_variable("x", 2));
+    let f: Expr = MultiplicationFunction::new(create_variable("x", 3), create_variable("x", 2)).into();
     let df = f.differentiate();
 
     print!("\nOutput1: {}\n\n", df.to_str());
@@ -0,0 +1,559 @@
+pub mod parser {
+    use std::fmt;
+    use crate::elementary::elementary::{create_constant, create_function};
+    use crate::rational::rational::Rational;
+    use crate::entity::entity::{create_number, create_variable, CompositionFunction, DivisionFunction, Entity, Expr, MultiplicationFunction, PowerFunction, SummationFunction};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Span {
+        pub start: usize,
+        pub end: usize,
+    }
+
+    impl Span {
+        pub fn new(start: usize, end: usize) -> Self {
+            Self { start, end }
+        }
+
+        pub fn join(&self, other: &Self) -> Self {
+            Self::new(self.start.min(other.start), self.end.max(other.end))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ParseError {
+        span: Span,
+        expected: String,
+        found: String,
+    }
+
+    impl ParseError {
+        pub fn new(span: Span, expected: &str, found: &str) -> Self {
+            Self { span, expected: expected.to_string(), found: found.to_string() }
+        }
+
+        pub fn span(&self) -> Span {
+            self.span
+        }
+
+        pub fn expected(&self) -> &str {
+            &self.expected
+        }
+
+        pub fn found(&self) -> &str {
+            &self.found
+        }
+
+        pub fn message(&self) -> String {
+            format!("expected {}, found {}", self.expected, self.found)
+        }
+
+        // Prints the line of `input` holding the error with carets under the span:
+        //
+        //   1 | (x + 1
+        //     | ^ expected a matching ')', found end of input
+        pub fn render(&self, input: &str) -> String {
+            let start = self.span.start.min(input.len());
+            let line_start = input[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let line_end = input[start..].find('\n').map(|i| start + i).unwrap_or(input.len());
+            let line_number = input[..line_start].matches('\n').count() + 1;
+
+            let column = input[line_start..start].chars().count();
+            let end = self.span.end.clamp(start, line_end);
+            let width = input[start..end].chars().count().max(1);
+
+            let gutter = " ".repeat(line_number.to_string().len());
+            format!(
+                "{} | {}\n{} | {}{} {}",
+                line_number, &input[line_start..line_end],
+                gutter, " ".repeat(column), "^".repeat(width), self.message()
+            )
+        }
+    }
+    impl fmt::Display for ParseError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} at {}..{}", self.message(), self.span.start, self.span.end)
+        }
+    }
+    impl std::error::Error for ParseError {}
+
+
+    //---- Tokens ----//
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        Number(Rational),
+        Identifier(String),
+        Plus,
+        Minus,
+        Star,
+        Slash,
+        Caret,
+        LeftParen,
+        RightParen,
+        End,
+    }
+
+    fn tokenize(input: &str) -> Result<Vec<(Token, Span)>, ParseError> {
+        let mut tokens: Vec<(Token, Span)> = vec![];
+        let mut chars = input.char_indices().peekable();
+
+        while let Some(&(start, c)) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+
+            if c.is_ascii_digit() || c == '.' {
+                let mut end = start;
+                while let Some(&(i, d)) = chars.peek() {
+                    if !d.is_ascii_digit() && d != '.' {
+                        break;
+                    }
+                    end = i + d.len_utf8();
+                    chars.next();
+                }
+
+                let span = Span::new(start, end);
+                let literal = &input[start..end];
+                match literal.parse::<Rational>() {
+                    Ok(value) => tokens.push((Token::Number(value), span)),
+                    Err(_) => return Err(ParseError::new(span, "a number", &format!("'{}'", literal))),
+                }
+                continue;
+            }
+
+            if c.is_alphabetic() || c == '_' {
+                let mut end = start;
+                while let Some(&(i, d)) = chars.peek() {
+                    if !d.is_alphanumeric() && d != '_' {
+                        break;
+                    }
+                    end = i + d.len_utf8();
+                    chars.next();
+                }
+
+                tokens.push((Token::Identifier(input[start..end].to_string()), Span::new(start, end)));
+                continue;
+            }
+
+            let span = Span::new(start, start + c.len_utf8());
+            let token = match c {
+                '+' => Token::Plus,
+                '-' => Token::Minus,
+                '*' => Token::Star,
+                '/' => Token::Slash,
+                '^' => Token::Caret,
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                _ => return Err(ParseError::new(span, "a number, variable, operator or parenthesis", &format!("'{}'", c))),
+            };
+            tokens.push((token, span));
+            chars.next();
+        }
+
+        tokens.push((Token::End, Span::new(input.len(), input.len())));
+        Ok(tokens)
+    }
+    ////// Tokens //////
+
+
+    //---- Syntax Tree ----//
+    #[derive(Debug, Clone)]
+    enum NodeKind {
+        Number(Rational),
+        Variable(String),
+        Negate(Box<Node>),
+        Add(Box<Node>, Box<Node>),
+        Subtract(Box<Node>, Box<Node>),
+        Multiply(Box<Node>, Box<Node>),
+        Divide(Box<Node>, Box<Node>),
+        Power(Box<Node>, Box<Node>),
+        Call(String, Box<Node>),
+    }
+
+    type BinaryKind = fn(Box<Node>, Box<Node>) -> NodeKind;
+
+    #[derive(Debug, Clone)]
+    struct Node {
+        kind: NodeKind,
+        span: Span,
+    }
+
+    impl Node {
+        fn new(kind: NodeKind, span: Span) -> Box<Self> {
+            Box::new(Self { kind, span })
+        }
+
+        fn binary(kind: BinaryKind, lhs: Box<Node>, rhs: Box<Node>) -> Box<Self> {
+            let span = lhs.span.join(&rhs.span);
+            Self::new(kind(lhs, rhs), span)
+        }
+    }
+
+    struct Parser<'a> {
+        source: &'a str,
+        tokens: Vec<(Token, Span)>,
+        current: usize,
+    }
+
+    impl Parser<'_> {
+        fn peek(&self) -> &Token {
+            &self.tokens[self.current].0
+        }
+
+        fn advance(&mut self) -> Span {
+            let span = self.tokens[self.current].1;
+            if *self.peek() != Token::End {
+                self.current += 1;
+            }
+            span
+        }
+
+        fn error(&self, expected: &str) -> ParseError {
+            let (token, span) = &self.tokens[self.current];
+            let found = match token {
+                Token::End => "end of input".to_string(),
+                _ => format!("'{}'", &self.source[span.start..span.end]),
+            };
+            ParseError::new(*span, expected, &found)
+        }
+
+        fn starts_operand(&self) -> bool {
+            matches!(self.peek(), Token::Number(_) | Token::Identifier(_) | Token::LeftParen)
+        }
+
+        // expression := term (('+' | '-') term)*
+        fn parse_expression(&mut self) -> Result<Box<Node>, ParseError> {
+            let mut lhs = self.parse_term()?;
+
+            loop {
+                let kind: BinaryKind = match self.peek() {
+                    Token::Plus => NodeKind::Add,
+                    Token::Minus => NodeKind::Subtract,
+                    _ => break,
+                };
+                self.advance();
+                lhs = Node::binary(kind, lhs, self.parse_term()?);
+            }
+
+            Ok(lhs)
+        }
+
+        // term := unary (('*' | '/')? unary)*
+        fn parse_term(&mut self) -> Result<Box<Node>, ParseError> {
+            let mut lhs = self.parse_unary()?;
+
+            loop {
+                let (kind, rhs): (BinaryKind, _) = match self.peek() {
+                    Token::Star => { self.advance(); (NodeKind::Multiply, self.parse_unary()?) },
+                    Token::Slash => { self.advance(); (NodeKind::Divide, self.parse_unary()?) },
+                    _ if self.starts_operand() => (NodeKind::Multiply, self.parse_power()?),
+                    _ => break,
+                };
+                lhs = Node::binary(kind, lhs, rhs);
+            }
+
+            Ok(lhs)
+        }
+
+        // unary := ('-' | '+') unary | power
+        fn parse_unary(&mut self) -> Result<Box<Node>, ParseError> {
+            match self.peek() {
+                Token::Minus => {
+                    let span = self.advance();
+                    let inner = self.parse_unary()?;
+                    let span = span.join(&inner.span);
+                    Ok(Node::new(NodeKind::Negate(inner), span))
+                },
+                Token::Plus => {
+                    self.advance();
+                    self.parse_unary()
+                },
+                _ => self.parse_power(),
+            }
+        }
+
+        // power := primary ('^' unary)?
+        fn parse_power(&mut self) -> Result<Box<Node>, ParseError> {
+            let base = self.parse_primary()?;
+
+            if *self.peek() == Token::Caret {
+                self.advance();
+                let exponent = self.parse_unary()?;
+                return Ok(Node::binary(NodeKind::Power, base, exponent));
+            }
+
+            Ok(base)
+        }
+
+        // primary := number | identifier | identifier parenthesized | parenthesized
+        fn parse_primary(&mut self) -> Result<Box<Node>, ParseError> {
+            match self.peek().clone() {
+                Token::Number(value) => {
+                    let span = self.advance();
+                    Ok(Node::new(NodeKind::Number(value), span))
+                },
+                Token::Identifier(name) => {
+                    let span = self.advance();
+                    if create_function(&name).is_some() {
+                        if *self.peek() != Token::LeftParen {
+                            return Err(self.error(&format!("'(' after '{}'", name)));
+                        }
+                        let argument = self.parse_parenthesized()?;
+                        let span = span.join(&argument.span);
+                        return Ok(Node::new(NodeKind::Call(name, argument), span));
+                    }
+
+                    // single letters before '(' are implicit multiplication, as in 2x(x+1)
+                    if *self.peek() == Token::LeftParen && name.chars().count() > 1 {
+                        return Err(ParseError::new(span, "a known function", &format!("'{}'", name)));
+                    }
+                    Ok(Node::new(NodeKind::Variable(name), span))
+                },
+                Token::LeftParen => self.parse_parenthesized(),
+                _ => Err(self.error("an operand")),
+            }
+        }
+
+        // parenthesized := '(' expression ')'
+        fn parse_parenthesized(&mut self) -> Result<Box<Node>, ParseError> {
+            let open = self.advance();
+            let inner = self.parse_expression()?;
+            if *self.peek() != Token::RightParen {
+                if *self.peek() == Token::End {
+                    return Err(ParseError::new(open, "a matching ')'", "end of input"));
+                }
+                return Err(self.error("')'"));
+            }
+            let close = self.advance();
+            Ok(Node::new(inner.kind, open.join(&close)))
+        }
+    }
+    ////// Syntax Tree //////
+
+
+    //---- Building ----//
+    fn constant_value(node: &Node) -> Option<Rational> {
+        match &node.kind {
+            NodeKind::Number(value) => Some(value.clone()),
+            NodeKind::Variable(_) => None,
+            NodeKind::Negate(inner) => constant_value(inner).map(|v| -v),
+            NodeKind::Add(a, b) => Some(constant_value(a)? + constant_value(b)?),
+            NodeKind::Subtract(a, b) => Some(constant_value(a)? - constant_value(b)?),
+            NodeKind::Multiply(a, b) => Some(constant_value(a)? * constant_value(b)?),
+            NodeKind::Divide(a, b) => {
+                let denominator = constant_value(b)?;
+                if denominator.is_zero() {
+                    return None;
+                }
+                Some(constant_value(a)? / denominator)
+            },
+            NodeKind::Power(a, b) => {
+                let base = constant_value(a)?;
+                // larger powers stay unfolded, as they do once collapsed, rather than be computed exactly
+                let exponent = integer_value(b).filter(|e| e.abs() <= 64)?;
+                if base.is_zero() && exponent < 0 {
+                    return None;
+                }
+                Some(base.pow(exponent))
+            },
+            NodeKind::Call(..) => None,
+        }
+    }
+
+    fn integer_value(node: &Node) -> Option<i32> {
+        i32::try_from(constant_value(node)?.to_i64()?).ok()
+    }
+
+    fn negate_entity(node: &Node, entity: Expr) -> Expr {
+        match constant_value(node) {
+            Some(value) => create_number(-value),
+            None => MultiplicationFunction::new(create_number(-1), entity).into(),
+        }
+    }
+
+    struct Builder<'a> {
+        source: &'a str,
+    }
+
+    impl Builder<'_> {
+        fn error(&self, node: &Node, expected: &str) -> ParseError {
+            let found = format!("'{}'", &self.source[node.span.start..node.span.end]);
+            ParseError::new(node.span, expected, &found)
+        }
+
+        fn collect_terms(&self, node: &Node, negate: bool, terms: &mut Vec<Expr>) -> Result<(), ParseError> {
+            match &node.kind {
+                NodeKind::Add(a, b) => {
+                    self.collect_terms(a, negate, terms)?;
+                    self.collect_terms(b, negate, terms)
+                },
+                NodeKind::Subtract(a, b) => {
+                    self.collect_terms(a, negate, terms)?;
+                    self.collect_terms(b, !negate, terms)
+                },
+                _ => {
+                    let term = self.build(node)?;
+                    terms.push(if negate { negate_entity(node, term) } else { term });
+                    Ok(())
+                },
+            }
+        }
+
+        fn build(&self, node: &Node) -> Result<Expr, ParseError> {
+            if let Some(value) = constant_value(node) {
+                return Ok(create_number(value));
+            }
+
+            match &node.kind {
+                NodeKind::Number(value) => Ok(create_number(value.clone())),
+                NodeKind::Variable(name) => Ok(create_constant(name).unwrap_or_else(|| create_variable(name, 1))),
+                NodeKind::Negate(inner) => Ok(negate_entity(inner, self.build(inner)?)),
+                NodeKind::Add(..) | NodeKind::Subtract(..) => {
+                    let mut terms: Vec<Expr> = vec![];
+                    self.collect_terms(node, false, &mut terms)?;
+                    Ok(SummationFunction::new(terms).into())
+                },
+                NodeKind::Multiply(a, b) => Ok(MultiplicationFunction::new(self.build(a)?, self.build(b)?).into()),
+                // a zero denominator is left for evaluate to report, as 0^-1 is
+                NodeKind::Divide(a, b) => Ok(DivisionFunction::new(self.build(a)?, self.build(b)?).into()),
+                NodeKind::Power(base, exponent) => {
+                    if let (NodeKind::Variable(name), Some(power)) = (&base.kind, constant_value(exponent)) {
+                        if create_constant(name).is_none() {
+                            return Ok(create_variable(name, power));
+                        }
+                    }
+                    Ok(PowerFunction::new(self.build(base)?, self.build(exponent)?).into())
+                },
+                NodeKind::Call(name, argument) => {
+                    match create_function(name) {
+                        Some(function) => Ok(CompositionFunction::new(function, self.build(argument)?).into()),
+                        None => Err(self.error(node, "a known function")),
+                    }
+                },
+            }
+        }
+    }
+    ////// Building //////
+
+
+    pub fn parse(input: &str) -> Result<Box<dyn Entity>, ParseError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { source: input, tokens, current: 0 };
+
+        let node = parser.parse_expression()?;
+        match parser.peek() {
+            Token::End => {},
+            Token::RightParen => {
+                let span = parser.tokens[parser.current].1;
+                return Err(ParseError::new(span, "an operator or end of input", "')' without a matching '('"));
+            },
+            _ => return Err(parser.error("an operator or end of input")),
+        }
+
+        let expr = Builder { source: input }.build(&node)?;
+        Ok(Box::new(expr))
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::collections::HashMap;
+        use crate::entity::entity::{EvalError, VariableIdentifier};
+
+        fn value(input: &str) -> f64 {
+            let env = HashMap::from([(VariableIdentifier::new("x"), 2.0), (VariableIdentifier::new("y"), 3.0), (VariableIdentifier::new("z"), 5.0)]);
+            parse(input).unwrap().evaluate(&env).unwrap()
+        }
+
+        fn failure(input: &str) -> ParseError {
+            parse(input).err().unwrap()
+        }
+
+        fn error(input: &str) -> (Span, String) {
+            let error = failure(input);
+            (error.span(), error.message())
+        }
+
+        #[test]
+        fn precedence() {
+            assert_eq!(value("1 + 2*x"), 5.0);
+            assert_eq!(value("1 + x/4"), 1.5);
+            assert_eq!(value("2*x^3"), 16.0);
+            assert_eq!(value("(1 + x)*y"), 9.0);
+            assert_eq!(value("-x^2"), -4.0);
+        }
+
+        #[test]
+        fn associativity() {
+            assert_eq!(value("z - y - x"), 0.0);
+            assert_eq!(value("z/x/4"), 0.625);
+            // powers group from the right
+            assert_eq!(value("x^y^x"), 512.0);
+            assert_eq!(parse("2^3^2").unwrap().to_str(), "512");
+        }
+
+        #[test]
+        fn implicit_multiplication() {
+            assert_eq!(value("2x(x + 1)"), 12.0);
+            assert_eq!(value("3x y"), 18.0);
+            assert_eq!(value("2 sqrt(4)"), 4.0);
+            assert_eq!(value("(x + 1)(y - 1)"), 6.0);
+            // implicit products group with division from the left, but powers bind tighter
+            assert_eq!(value("12/2x"), 12.0);
+            assert_eq!(value("2x^2"), 8.0);
+        }
+
+        #[test]
+        fn unary_minus() {
+            assert_eq!(value("-x"), -2.0);
+            assert_eq!(value("--x"), 2.0);
+            assert_eq!(value("y*-x"), -6.0);
+            assert_eq!(value("x^-1"), 0.5);
+            assert_eq!(value("+x - -y"), 5.0);
+        }
+
+        #[test]
+        fn constants_fold_with_small_exponents() {
+            assert_eq!(parse("2^64").unwrap().to_str(), "18446744073709551616");
+            assert_eq!(parse("2^65").unwrap().to_str(), "2^65");
+            assert_eq!(parse("9^999999999").unwrap().to_str(), "9^999999999");
+            assert_eq!(parse("(1/2)^-3 + 1").unwrap().to_str(), "9");
+        }
+
+        #[test]
+        fn errors_point_at_the_offending_token() {
+            assert_eq!(error("x + * y"), (Span::new(4, 5), "expected an operand, found '*'".to_string()));
+            assert_eq!(error("(x + 1"), (Span::new(0, 1), "expected a matching ')', found end of input".to_string()));
+            assert_eq!(error("x + 1)"), (Span::new(5, 6), "expected an operator or end of input, found ')' without a matching '('".to_string()));
+            assert_eq!(error("foo(x)"), (Span::new(0, 3), "expected a known function, found 'foo'".to_string()));
+            assert_eq!(error("sin x"), (Span::new(4, 5), "expected '(' after 'sin', found 'x'".to_string()));
+            assert_eq!(error("1 + ln"), (Span::new(6, 6), "expected '(' after 'ln', found end of input".to_string()));
+            assert_eq!(error("x $ y"), (Span::new(2, 3), "expected a number, variable, operator or parenthesis, found '$'".to_string()));
+            assert_eq!(error("1.2.3"), (Span::new(0, 5), "expected a number, found '1.2.3'".to_string()));
+        }
+
+        #[test]
+        fn render_puts_carets_under_the_span() {
+            let input = "x^2 +\n(x + 1";
+            assert_eq!(failure(input).render(input), "2 | (x + 1\n  | ^ expected a matching ')', found end of input");
+
+            let input = "1 + foo(x)";
+            assert_eq!(failure(input).render(input), "1 | 1 + foo(x)\n  |     ^^^ expected a known function, found 'foo'");
+        }
+
+        #[test]
+        fn zero_denominators_parse_and_fail_to_evaluate() {
+            let env = HashMap::from([(VariableIdentifier::new("x"), 2.0)]);
+            for input in ["1/(2 - 2)", "1/0", "0^-1", "x/(x - x)", "(x + 1)/0 + 2"] {
+                let mut expr = parse(input).unwrap().to_expr();
+                assert_eq!(expr.evaluate(&env), Err(EvalError::DivisionByZero), "{}", input);
+
+                // what collapse prints parses back to the same failure
+                expr.collapse();
+                assert_eq!(parse(&expr.to_str()).unwrap().evaluate(&env), Err(EvalError::DivisionByZero), "{}", expr.to_str());
+            }
+        }
+    }
+}
@@ -0,0 +1,799 @@
+pub mod polynomial {
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+    use crate::bigint::bigint::BigInt;
+    use crate::rational::rational::Rational;
+    use crate::canonical::canonical::{canonicalize, expand, factor_product, factor_sum, factored, PowerList};
+    use crate::entity::entity::{as_number, create_number, create_variable, ConstantTerm, Entity, EvalError, Expr, SummationFunction, VariableEntity, VariableIdentifier, VariableTerm};
+
+    //---- Univariate Polynomials ----//
+    // coefficients from the constant term up, without trailing zeros
+    pub type Coefficients = Vec<Rational>;
+
+    pub fn trimmed(mut polynomial: Coefficients) -> Coefficients {
+        while polynomial.last().is_some_and(Rational::is_zero) {
+            polynomial.pop();
+        }
+        polynomial
+    }
+
+    pub fn degree(polynomial: &Coefficients) -> usize {
+        polynomial.len().saturating_sub(1)
+    }
+
+    pub fn leading(polynomial: &Coefficients) -> Rational {
+        polynomial.last().cloned().unwrap_or_else(Rational::zero)
+    }
+
+    pub fn scale(polynomial: &Coefficients, factor: &Rational) -> Coefficients {
+        trimmed(polynomial.iter().map(|coefficient| coefficient * factor).collect())
+    }
+
+    pub fn subtract(first: &Coefficients, second: &Coefficients) -> Coefficients {
+        let zero = Rational::zero();
+        let difference = (0..first.len().max(second.len()))
+            .map(|i| first.get(i).unwrap_or(&zero) - second.get(i).unwrap_or(&zero))
+            .collect();
+        trimmed(difference)
+    }
+
+    pub fn multiply(first: &Coefficients, second: &Coefficients) -> Coefficients {
+        if first.is_empty() || second.is_empty() {
+            return vec![];
+        }
+
+        let mut product = vec![Rational::zero(); first.len() + second.len() - 1];
+        for (i, a) in first.iter().enumerate() {
+            for (j, b) in second.iter().enumerate() {
+                product[i + j] = &product[i + j] + &(a * b);
+            }
+        }
+        trimmed(product)
+    }
+
+    pub fn divide(dividend: &Coefficients, divisor: &Coefficients) -> (Coefficients, Coefficients) {
+        let mut remainder = dividend.clone();
+        if divisor.len() > dividend.len() {
+            return (vec![], remainder);
+        }
+
+        let mut quotient = vec![Rational::zero(); dividend.len() - divisor.len() + 1];
+        let lead = leading(divisor);
+        while !remainder.is_empty() && remainder.len() >= divisor.len() {
+            let shift = remainder.len() - divisor.len();
+            let coefficient = &leading(&remainder) / &lead;
+            for (i, term) in divisor.iter().enumerate() {
+                remainder[shift + i] = &remainder[shift + i] - &(term * &coefficient);
+            }
+            quotient[shift] = coefficient;
+            remainder.pop();
+            remainder = trimmed(remainder);
+        }
+        (trimmed(quotient), remainder)
+    }
+
+    pub fn derivative(polynomial: &Coefficients) -> Coefficients {
+        let derivative = polynomial.iter().enumerate().skip(1).map(|(i, coefficient)| coefficient * &Rational::from(i as i64)).collect();
+        trimmed(derivative)
+    }
+
+    pub fn evaluate(polynomial: &Coefficients, at: &Rational) -> Rational {
+        polynomial.iter().rev().fold(Rational::zero(), |value, coefficient| &(&value * at) + coefficient)
+    }
+
+    // the monic greatest common divisor. Every remainder is taken primitive, which keeps its
+    // coefficients from growing with each step
+    pub fn gcd(first: &Coefficients, second: &Coefficients) -> Coefficients {
+        let (mut a, mut b) = (primitive(first).1, primitive(second).1);
+        while !b.is_empty() {
+            let (_, remainder) = divide(&a, &b);
+            a = b;
+            b = primitive(&remainder).1;
+        }
+        let lead = leading(&a);
+        if lead.is_zero() { a } else { scale(&a, &lead.recip()) }
+    }
+
+    // the content and the primitive part, i.e. integer coefficients without a common factor and a positive leading one
+    pub fn primitive(polynomial: &Coefficients) -> (Rational, Coefficients) {
+        let denominators = polynomial.iter().fold(BigInt::one(), |lcm, coefficient| {
+            let denominator = coefficient.denominator();
+            &(&lcm * denominator) / &lcm.gcd(denominator)
+        });
+        let numerators = polynomial.iter().fold(BigInt::zero(), |gcd, coefficient| gcd.gcd(coefficient.numerator()));
+
+        let mut content = Rational::new(numerators, denominators);
+        if leading(polynomial).is_negative() {
+            content = -content;
+        }
+        if content.is_zero() {
+            return (content, vec![]);
+        }
+        (content.clone(), scale(polynomial, &content.recip()))
+    }
+    ////// Univariate Polynomials //////
+
+
+    //---- Multivariate Polynomials ----//
+    // the powers of a polynomial's variables in one of its terms, in the same order
+    pub type Exponents = Vec<u32>;
+
+    // A sparse polynomial with exact coefficients, keyed by the exponents of its variables in name
+    // order. Only variables that occur are kept, and no coefficient is zero, so that equal
+    // polynomials are equal structurally. Exponents compare lexicographically, so the greatest
+    // term has the highest power of the first variable: x^2 > x*y > x > y^2 > y > 1
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+    pub struct Polynomial {
+        variables: Vec<VariableIdentifier>,
+        terms: BTreeMap<Exponents, Rational>,
+    }
+
+    impl Polynomial {
+        pub fn zero() -> Self {
+            Self::default()
+        }
+
+        pub fn constant(value: Rational) -> Self {
+            let mut constant = Self::zero();
+            constant.add_term(vec![], value);
+            constant
+        }
+
+        pub fn variable(variable: VariableIdentifier) -> Self {
+            Self { variables: vec![variable], terms: BTreeMap::from([(vec![1], Rational::one())]) }
+        }
+
+        pub fn variables(&self) -> &[VariableIdentifier] {
+            &self.variables
+        }
+
+        pub fn terms(&self) -> &BTreeMap<Exponents, Rational> {
+            &self.terms
+        }
+
+        pub fn is_zero(&self) -> bool {
+            self.terms.is_empty()
+        }
+
+        pub fn is_constant(&self) -> bool {
+            self.variables.is_empty()
+        }
+
+        // the greatest term
+        pub fn leading(&self) -> Option<(&Exponents, &Rational)> {
+            self.terms.last_key_value()
+        }
+
+        pub fn degree(&self, variable: &VariableIdentifier) -> u32 {
+            match self.position(variable) {
+                Some(i) => self.terms.keys().map(|exponents| exponents[i]).max().unwrap_or(0),
+                None => 0,
+            }
+        }
+
+        // the coefficient of variable^power, as a polynomial in the other variables
+        pub fn coefficient(&self, variable: &VariableIdentifier, power: u32) -> Self {
+            let Some(i) = self.position(variable) else {
+                return if power == 0 { self.clone() } else { Self::zero() };
+            };
+
+            let mut variables = self.variables.clone();
+            variables.remove(i);
+            let terms = self.terms
+                .iter()
+                .filter(|(exponents, _)| exponents[i] == power)
+                .map(|(exponents, coefficient)| {
+                    let mut exponents = exponents.clone();
+                    exponents.remove(i);
+                    (exponents, coefficient.clone())
+                })
+                .collect();
+            Self { variables, terms }.pruned()
+        }
+
+        pub fn add(&self, other: &Self) -> Self {
+            let mut sum = self.clone();
+            sum.accumulate(other);
+            sum.pruned()
+        }
+
+        pub fn subtract(&self, other: &Self) -> Self {
+            self.add(&other.scale(&-Rational::one()))
+        }
+
+        pub fn multiply(&self, other: &Self) -> Self {
+            let (first, second) = Self::aligned(self, other);
+            let mut product = Self { variables: first.variables.clone(), terms: BTreeMap::new() };
+            for (a, x) in &first.terms {
+                for (b, y) in &second.terms {
+                    product.add_term(a.iter().zip(b).map(|(a, b)| a + b).collect(), x * y);
+                }
+            }
+            product.pruned()
+        }
+
+        pub fn scale(&self, factor: &Rational) -> Self {
+            let terms = self.terms.iter().map(|(exponents, coefficient)| (exponents.clone(), coefficient * factor)).filter(|(_, coefficient)| !coefficient.is_zero()).collect();
+            Self { variables: self.variables.clone(), terms }.pruned()
+        }
+
+        // by repeated squaring
+        pub fn pow(&self, exponent: u32) -> Self {
+            let (mut result, mut base, mut exponent) = (Self::constant(Rational::one()), self.clone(), exponent);
+            while exponent > 0 {
+                if exponent % 2 == 1 {
+                    result = result.multiply(&base);
+                }
+                exponent /= 2;
+                if exponent > 0 {
+                    base = base.multiply(&base);
+                }
+            }
+            result
+        }
+
+        pub fn differentiate(&self, variable: &VariableIdentifier) -> Self {
+            let Some(i) = self.position(variable) else {
+                return Self::zero();
+            };
+
+            let mut derivative = Self { variables: self.variables.clone(), terms: BTreeMap::new() };
+            for (exponents, coefficient) in self.terms.iter().filter(|(exponents, _)| exponents[i] > 0) {
+                let mut exponents = exponents.clone();
+                let power = Rational::from(exponents[i] as i64);
+                exponents[i] -= 1;
+                derivative.add_term(exponents, coefficient * &power);
+            }
+            derivative.pruned()
+        }
+
+        pub fn evaluate(&self, env: &HashMap<VariableIdentifier, f64>) -> Result<f64, EvalError> {
+            let values = self.variables
+                .iter()
+                .map(|variable| env.get(variable).copied().ok_or_else(|| EvalError::UnboundVariable(variable.clone())))
+                .collect::<Result<Vec<f64>, EvalError>>()?;
+
+            let mut sum = 0.0;
+            for (exponents, coefficient) in &self.terms {
+                sum += exponents.iter().zip(&values).fold(coefficient.to_f64(), |product, (&power, value)| product * value.powi(power as i32));
+            }
+            Ok(sum)
+        }
+
+        // Long division in lexicographic order: the quotient and a remainder none of whose terms is
+        // divisible by the leading term of `divisor`
+        pub fn divide(&self, divisor: &Self) -> (Self, Self) {
+            if divisor.is_zero() {
+                return (Self::zero(), self.clone());
+            }
+
+            let (mut rest, divisor) = Self::aligned(self, divisor);
+            let (lead, lead_coefficient) = divisor.leading().unwrap();
+            let mut quotient = Self { variables: rest.variables.clone(), terms: BTreeMap::new() };
+            let mut remainder = quotient.clone();
+            while let Some((exponents, coefficient)) = rest.terms.pop_last() {
+                let factor: Option<Exponents> = exponents.iter().zip(lead).map(|(a, b)| a.checked_sub(*b)).collect();
+                let Some(factor) = factor else {
+                    remainder.add_term(exponents, coefficient);
+                    continue;
+                };
+
+                // the rest of the divisor times the step, as the step's lead cancels the term just taken off
+                let step = &coefficient / lead_coefficient;
+                for (exponents, coefficient) in divisor.terms.iter().rev().skip(1) {
+                    rest.add_term(exponents.iter().zip(&factor).map(|(a, b)| a + b).collect(), -(coefficient * &step));
+                }
+                quotient.add_term(factor, step);
+            }
+            (quotient.pruned(), remainder.pruned())
+        }
+
+        // lc(divisor)^(deg(self) - deg(divisor) + 1) * self reduced modulo `divisor`, both taken as
+        // polynomials in `variable`, which needs no division of the coefficients
+        pub fn pseudo_remainder(&self, divisor: &Self, variable: &VariableIdentifier) -> Self {
+            let degree = divisor.degree(variable);
+            let lead = divisor.coefficient(variable, degree);
+
+            let mut remainder = self.clone();
+            let mut steps = (self.degree(variable) + 1).saturating_sub(degree);
+            while !remainder.is_zero() && remainder.degree(variable) >= degree && steps > 0 {
+                let shift = remainder.degree(variable) - degree;
+                let factor = remainder.coefficient(variable, shift + degree).multiply(&Self::variable(variable.clone()).pow(shift));
+                remainder = lead.multiply(&remainder).subtract(&factor.multiply(divisor));
+                steps -= 1;
+            }
+            remainder.multiply(&lead.pow(steps))
+        }
+
+        // the greatest common divisor of the coefficients in `variable`
+        pub fn content(&self, variable: &VariableIdentifier) -> Self {
+            (0..=self.degree(variable)).fold(Self::zero(), |content, power| content.gcd(&self.coefficient(variable, power)))
+        }
+
+        pub fn primitive_part(&self, variable: &VariableIdentifier) -> Self {
+            self.divide(&self.content(variable)).0
+        }
+
+        // The greatest common divisor with a positive leading coefficient, by recursion on the
+        // variables: the gcd of the contents times the last nonzero member of the primitive
+        // remainder sequence. For numbers it is the gcd of the numerators over the lcm of the
+        // denominators, so that taking the content keeps the remainders' coefficients small.
+        // The result keeps that rational content, e.g. (x + 1)/2 for x/2 + 1/2 and x + 1, so
+        // callers that divide by it to cancel a factor take its normalized() part
+        pub fn gcd(&self, other: &Self) -> Self {
+            if self.is_zero() {
+                return other.with_positive_lead();
+            }
+            if other.is_zero() {
+                return self.with_positive_lead();
+            }
+            if self.is_constant() || other.is_constant() {
+                let numbers: Coefficients = self.terms.values().chain(other.terms.values()).cloned().collect();
+                return Self::constant(primitive(&numbers).0.abs());
+            }
+
+            // a variable that only one of them has is not in the gcd, which then divides each of its coefficients in it
+            for (first, second) in [(self, other), (other, self)] {
+                if let Some(variable) = first.variables.iter().find(|variable| second.position(variable).is_none()) {
+                    return (0..=first.degree(variable)).fold(second.clone(), |gcd, power| gcd.gcd(&first.coefficient(variable, power)));
+                }
+            }
+
+            let variable = self.variables[0].clone();
+
+            let (first_content, second_content) = (self.content(&variable), other.content(&variable));
+            let content = first_content.gcd(&second_content);
+
+            let mut first = self.divide(&first_content).0;
+            let mut second = other.divide(&second_content).0;
+            if first.degree(&variable) < second.degree(&variable) {
+                std::mem::swap(&mut first, &mut second);
+            }
+            while !second.is_zero() && second.degree(&variable) > 0 {
+                let remainder = first.pseudo_remainder(&second, &variable);
+                first = second;
+                second = if remainder.is_zero() { remainder } else { remainder.primitive_part(&variable) };
+            }
+
+            let common = if second.is_zero() { first.primitive_part(&variable) } else { Self::constant(Rational::one()) };
+            content.multiply(&common).with_positive_lead()
+        }
+
+        // scaled to coprime integer coefficients with a positive leading one
+        pub fn normalized(&self) -> Self {
+            Self::normalized_pair(&Self::zero(), self).1
+        }
+
+        // both sides of a quotient scaled alike, to coprime integer coefficients with a positive
+        // leading coefficient in the denominator
+        pub fn normalized_pair(numerator: &Self, denominator: &Self) -> (Self, Self) {
+            if numerator.is_zero() && denominator.is_zero() {
+                return (Self::zero(), Self::zero());
+            }
+            // the leading coefficient of the denominator goes last, so that it decides the sign
+            let coefficients: Coefficients = numerator.terms.values().chain(denominator.terms.values()).cloned().collect();
+            let scale = primitive(&coefficients).0.recip();
+            (numerator.scale(&scale), denominator.scale(&scale))
+        }
+
+        fn with_positive_lead(&self) -> Self {
+            match self.leading() {
+                Some((_, lead)) if lead.is_negative() => self.scale(&-Rational::one()),
+                _ => self.clone(),
+            }
+        }
+
+        // adds `other` in place, over the union of the variables, leaving any unused ones for pruning
+        fn accumulate(&mut self, other: &Self) {
+            if other.variables.iter().any(|variable| self.position(variable).is_none()) {
+                *self = Self::aligned(self, other).0;
+            }
+            for (exponents, coefficient) in other.with_variables(&self.variables).terms {
+                self.add_term(exponents, coefficient);
+            }
+        }
+
+        fn position(&self, variable: &VariableIdentifier) -> Option<usize> {
+            self.variables.binary_search(variable).ok()
+        }
+
+        // both polynomials over the union of their variables
+        fn aligned(first: &Self, second: &Self) -> (Self, Self) {
+            if first.variables == second.variables {
+                return (first.clone(), second.clone());
+            }
+            let variables: BTreeSet<&VariableIdentifier> = first.variables.iter().chain(&second.variables).collect();
+            let variables: Vec<VariableIdentifier> = variables.into_iter().cloned().collect();
+            (first.with_variables(&variables), second.with_variables(&variables))
+        }
+
+        // the same polynomial over `variables`, which include all of its own
+        fn with_variables(&self, variables: &[VariableIdentifier]) -> Self {
+            if self.variables == variables {
+                return self.clone();
+            }
+            let positions: Vec<Option<usize>> = variables.iter().map(|variable| self.position(variable)).collect();
+            let terms = self.terms
+                .iter()
+                .map(|(exponents, coefficient)| (positions.iter().map(|i| i.map_or(0, |i| exponents[i])).collect(), coefficient.clone()))
+                .collect();
+            Self { variables: variables.to_vec(), terms }
+        }
+
+        // without the variables that no term has a power of
+        fn pruned(self) -> Self {
+            let used: Vec<bool> = (0..self.variables.len()).map(|i| self.terms.keys().any(|exponents| exponents[i] > 0)).collect();
+            if used.iter().all(|&used| used) {
+                return self;
+            }
+
+            let variables = self.variables.iter().zip(&used).filter(|(_, &used)| used).map(|(variable, _)| variable.clone()).collect();
+            let terms = self.terms
+                .into_iter()
+                .map(|(exponents, coefficient)| (exponents.into_iter().zip(&used).filter(|(_, &used)| used).map(|(power, _)| power).collect(), coefficient))
+                .collect();
+            Self { variables, terms }
+        }
+
+        fn add_term(&mut self, exponents: Exponents, coefficient: Rational) {
+            let sum = match self.terms.get(&exponents) {
+                Some(existing) => existing + &coefficient,
+                None => coefficient,
+            };
+
+            if sum.is_zero() {
+                self.terms.remove(&exponents);
+            }
+            else {
+                self.terms.insert(exponents, sum);
+            }
+        }
+    }
+    ////// Multivariate Polynomials //////
+
+
+    //---- Expressions ----//
+    // the variable and coefficients of `expr` when it expands to a polynomial in a single variable
+    pub fn univariate(expr: &Expr) -> Option<(Option<VariableIdentifier>, Coefficients)> {
+        let expanded = expand(expr);
+        let terms = match &expanded {
+            Expr::Sum(sum) => sum.terms().to_vec(),
+            _ => vec![expanded.clone()],
+        };
+
+        let mut variable: Option<VariableIdentifier> = None;
+        let mut polynomial = vec![];
+        for term in &terms {
+            let (power, coefficient) = match term {
+                Expr::Constant(_) => (0, as_number(term)?),
+                Expr::Variable(monomial) => {
+                    let var = monomial.variable();
+                    if variable.as_ref().is_some_and(|variable| variable != var.variable()) {
+                        return None;
+                    }
+                    variable = Some(var.variable().clone());
+
+                    let power = var.power().to_i64().filter(|power| *power > 0)? as usize;
+                    let coefficient = monomial.coeffs().iter().try_fold(Rational::one(), |product, coeff| Some(&product * &as_number(coeff)?))?;
+                    (power, coefficient)
+                },
+                _ => return None,
+            };
+
+            if polynomial.len() <= power {
+                polynomial.resize(power + 1, Rational::zero());
+            }
+            polynomial[power] = &polynomial[power] + &coefficient;
+        }
+        Some((variable, trimmed(polynomial)))
+    }
+
+    pub fn polynomial_expr(variable: &VariableIdentifier, polynomial: &Coefficients) -> Expr {
+        let terms: Vec<Expr> = polynomial
+            .iter()
+            .enumerate()
+            .filter(|(_, coefficient)| !coefficient.is_zero())
+            .map(|(power, coefficient)| match power {
+                0 => create_number(coefficient.clone()),
+                _ => VariableTerm::new(VariableEntity::new(variable.clone(), Rational::from(power as i64)), vec![create_number(coefficient.clone())]).into(),
+            })
+            .collect();
+        canonicalize(&SummationFunction::new(terms).into())
+    }
+
+    fn natural(power: &Rational) -> Option<u32> {
+        power.to_i64().and_then(|power| u32::try_from(power).ok())
+    }
+
+    fn variable_power(var: &VariableEntity) -> Option<Polynomial> {
+        Some(Polynomial::variable(var.variable().clone()).pow(natural(var.power())?))
+    }
+
+    impl Polynomial {
+        // `expr` as a polynomial, when it is built from numbers and natural powers of variables
+        // with sums, products, natural powers and division by numbers
+        pub fn from_expr(expr: &Expr) -> Option<Self> {
+            Self::from_expr_by(expr, &mut |_| None)
+        }
+
+        // the same, with the parts that are not polynomials handed to `opaque`
+        fn from_expr_by(expr: &Expr, opaque: &mut dyn FnMut(&Expr) -> Option<Self>) -> Option<Self> {
+            match expr {
+                Expr::Constant(constant) => {
+                    let value = Self::constant(constant.value().clone());
+                    match constant.non_wrt_variables().iter().try_fold(value, |product, var| Some(product.multiply(&variable_power(var)?))) {
+                        Some(polynomial) => Some(polynomial),
+                        None => opaque(expr),
+                    }
+                },
+                Expr::Variable(term) => {
+                    let power = match variable_power(term.variable()) {
+                        Some(power) => power,
+                        None => opaque(&VariableTerm::new(term.variable().clone(), vec![]).into())?,
+                    };
+                    term.coeffs().iter().try_fold(power, |product, coeff| Some(product.multiply(&Self::from_expr_by(coeff, opaque)?)))
+                },
+                Expr::Sum(sum) => {
+                    let mut total = Self::zero();
+                    for term in sum.terms() {
+                        total.accumulate(&Self::from_expr_by(term, opaque)?);
+                    }
+                    Some(total.pruned())
+                },
+                Expr::Product(product) => Some(Self::from_expr_by(product.first(), opaque)?.multiply(&Self::from_expr_by(product.second(), opaque)?)),
+                Expr::Quotient(quotient) => {
+                    match Self::from_expr(quotient.denominator()).filter(Self::is_constant).and_then(|denominator| denominator.leading().map(|(_, value)| value.recip())) {
+                        Some(scale) => Some(Self::from_expr_by(quotient.numerator(), opaque)?.scale(&scale)),
+                        None => opaque(expr),
+                    }
+                },
+                Expr::Power(power) => match as_number(power.exponent()).as_ref().and_then(natural) {
+                    Some(exponent) => Some(Self::from_expr_by(power.base(), opaque)?.pow(exponent)),
+                    None => opaque(expr),
+                },
+                Expr::Composition(_) | Expr::Named(_) => opaque(expr),
+            }
+        }
+
+        // The canonical form of the polynomial: terms in graded lexicographic order, each led by
+        // its first variable with the others as the non-wrt variables of its coefficient
+        pub fn to_expr(&self) -> Expr {
+            let mut terms: Vec<(&Exponents, &Rational)> = self.terms.iter().collect();
+            terms.sort_by(|(a, _), (b, _)| b.iter().sum::<u32>().cmp(&a.iter().sum::<u32>()).then_with(|| b.cmp(a)));
+
+            let mut terms: Vec<Expr> = terms
+                .into_iter()
+                .map(|(exponents, coefficient)| {
+                    let mut variables = self.variables
+                        .iter()
+                        .zip(exponents)
+                        .filter(|(_, &power)| power > 0)
+                        .map(|(variable, &power)| VariableEntity::new(variable.clone(), Rational::from(power as i64)));
+                    match variables.next() {
+                        None => create_number(coefficient.clone()),
+                        Some(first) => VariableTerm::new(first, vec![ConstantTerm::new(coefficient.clone(), variables.collect()).into()]).into(),
+                    }
+                })
+                .collect();
+            match terms.len() {
+                0 => create_number(0),
+                1 => terms.remove(0),
+                _ => SummationFunction::new(terms).into(),
+            }
+        }
+
+        pub fn from_entity(entity: &dyn Entity) -> Option<Self> {
+            Self::from_expr(&entity.to_expr())
+        }
+
+        pub fn to_entity(&self) -> Box<dyn Entity> {
+            Box::new(self.to_expr())
+        }
+    }
+
+    // stand-ins for the parts of an expression that are not polynomials, such as ln(x) or x^(1/2)
+    fn symbol(index: usize) -> VariableIdentifier {
+        VariableIdentifier::new(&format!("#{}", index))
+    }
+
+    // `expr` as a polynomial in its variables and symbols for its other parts, which are added to `atoms`
+    fn symbolic(expr: &Expr, atoms: &mut Vec<Expr>) -> Polynomial {
+        let mut opaque = |atom: &Expr| {
+            let index = atoms.iter().position(|known| known == atom).unwrap_or_else(|| {
+                atoms.push(atom.clone());
+                atoms.len() - 1
+            });
+            Some(Polynomial::variable(symbol(index)))
+        };
+        Polynomial::from_expr_by(expr, &mut opaque).unwrap()
+    }
+
+    // the expression that `symbolic` turned into `polynomial`, in canonical form
+    fn restored(polynomial: &Polynomial, atoms: &[Expr]) -> Expr {
+        let bases: Vec<Expr> = polynomial.variables
+            .iter()
+            .map(|variable| match (0..atoms.len()).find(|&index| symbol(index) == *variable) {
+                Some(index) => atoms[index].clone(),
+                None => create_variable(variable.name(), 1),
+            })
+            .collect();
+
+        let products = polynomial.terms
+            .iter()
+            .map(|(exponents, coefficient)| {
+                let factors = bases.iter().zip(exponents).filter(|(_, &power)| power > 0).map(|(base, &power)| (base.clone(), Rational::from(power as i64)));
+                (coefficient.clone(), factors.collect())
+            })
+            .collect();
+        factor_sum(products)
+    }
+
+    // Numerator/denominator in lowest terms, with the numerator expanded. Parts that are not
+    // polynomials, such as ln(x), are taken as symbols of their own. The denominator stays a
+    // product of powers of its expanded factors, each with coprime integer coefficients and a
+    // positive leading one. Nothing cancels from a zero numerator or against a zero denominator
+    pub fn cancel(numerator: &Expr, denominator: &Expr) -> Option<(Expr, Expr)> {
+        let (mut coefficient, factors) = factored(denominator);
+        if factors.is_empty() || coefficient.is_zero() {
+            return None;
+        }
+
+        let mut atoms = vec![];
+        let factors: Vec<(Polynomial, u32)> = factors
+            .into_iter()
+            .map(|(base, power)| match natural(&power) {
+                Some(power) => (symbolic(&base, &mut atoms), power),
+                None => (symbolic(&factor_product(Rational::one(), vec![(base, power)]), &mut atoms), 1),
+            })
+            .collect();
+
+        let mut top = symbolic(numerator, &mut atoms);
+        if top.is_zero() || factors.iter().any(|(polynomial, _)| polynomial.is_zero()) {
+            return None;
+        }
+
+        let mut remaining: PowerList<Expr> = vec![];
+        for (polynomial, mut power) in factors {
+            let normal = polynomial.normalized();
+            let scale = polynomial.leading().unwrap().1 / normal.leading().unwrap().1;
+            coefficient = &coefficient * &scale.pow(power as i32);
+
+            // what the numerator shares with the factor cancels once per power, leaving the rest of the
+            // factor. The gcd can carry a rational content, so only its primitive part is divided out
+            while power > 0 {
+                let common = top.gcd(&normal).normalized();
+                if common.is_constant() {
+                    break;
+                }
+                top = top.divide(&common).0;
+                let rest = normal.divide(&common).0;
+                if rest.is_constant() {
+                    coefficient = &coefficient * rest.leading().unwrap().1;
+                }
+                else {
+                    remaining.push((restored(&rest, &atoms), Rational::one()));
+                }
+                power -= 1;
+            }
+            if power > 0 {
+                remaining.push((restored(&normal, &atoms), Rational::from(power as i64)));
+            }
+        }
+
+        let (top, coefficient) = Polynomial::normalized_pair(&top, &Polynomial::constant(coefficient));
+        Some((restored(&top, &atoms), factor_product(coefficient.leading().unwrap().1.clone(), remaining)))
+    }
+    ////// Expressions //////
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::parser::parser::parse;
+
+        fn polynomial(input: &str) -> Polynomial {
+            Polynomial::from_entity(parse(input).unwrap().as_ref()).unwrap()
+        }
+
+        fn x() -> VariableIdentifier {
+            VariableIdentifier::new("x")
+        }
+
+        // Knuth, TAOCP vol. 2, 4.6.1
+        const KNUTH_FIRST: &str = "x^8 + x^6 - 3*x^4 - 3*x^3 + 8*x^2 + 2*x - 5";
+        const KNUTH_SECOND: &str = "3*x^6 + 5*x^4 - 4*x^2 - 9*x + 21";
+
+        #[test]
+        fn expressions_round_trip() {
+            let original = polynomial("2/3*x^3*y - 5/2*x*y^2*z + y/7 - 3/4");
+            let expr = original.to_expr();
+            assert_eq!(Polynomial::from_expr(&expr), Some(original.clone()));
+            assert_eq!(Polynomial::from_expr(&canonicalize(&expr)), Some(original.clone()));
+
+            let env = HashMap::from([(x(), 1.5), (VariableIdentifier::new("y"), -2.0), (VariableIdentifier::new("z"), 0.5)]);
+            assert!((expr.evaluate(&env).unwrap() - original.evaluate(&env).unwrap()).abs() < 1e-12);
+
+            // parts that are not polynomials come back from their symbols
+            let mut atoms = vec![];
+            let expr = parse("ln(x)^2/2 + 3/5*x*ln(x) + sqrt(y)").unwrap().to_expr();
+            let symbolic = symbolic(&expr, &mut atoms);
+            assert_eq!(atoms.len(), 2);
+            assert_eq!(restored(&symbolic, &atoms), canonicalize(&expr));
+        }
+
+        #[test]
+        fn pseudo_remainder_scales_by_the_leading_coefficient() {
+            let remainder = polynomial(KNUTH_FIRST).pseudo_remainder(&polynomial(KNUTH_SECOND), &x());
+            assert_eq!(remainder, polynomial("-15*x^4 + 3*x^2 - 9"));
+            assert_eq!(remainder.primitive_part(&x()), polynomial("-5*x^4 + x^2 - 3"));
+        }
+
+        #[test]
+        fn gcd_of_coprime_polynomials_is_one() {
+            assert_eq!(polynomial(KNUTH_FIRST).gcd(&polynomial(KNUTH_SECOND)), polynomial("1"));
+            assert_eq!(polynomial("(x + 2)^14 + x").gcd(&polynomial("(x + 3)^13 + 1")), polynomial("1"));
+        }
+
+        #[test]
+        fn gcd_keeps_common_factors_and_content() {
+            assert_eq!(polynomial("(x + 1)^2*(x - 2)").gcd(&polynomial("(x + 1)*(x + 3)")), polynomial("x + 1"));
+            assert_eq!(polynomial("x^2 - y^2").gcd(&polynomial("x^2 + 2*x*y + y^2")), polynomial("x + y"));
+            assert_eq!(polynomial("6*x + 6").gcd(&polynomial("-4*x - 4")), polynomial("2*x + 2"));
+            assert_eq!(polynomial("x/2 + 1/2").gcd(&polynomial("x + 1")), polynomial("x/2 + 1/2"));
+            assert_eq!(polynomial("0").gcd(&polynomial("-x")), polynomial("x"));
+            assert_eq!(polynomial("x*y + x").gcd(&polynomial("x^2")), polynomial("x"));
+            assert_eq!(polynomial("2*x*y + 4*y").gcd(&polynomial("3*x + 6")), polynomial("x + 2"));
+        }
+
+        #[test]
+        fn cancel_divides_out_the_gcd_without_its_content() {
+            let cancelled = |numerator: &str, denominator: &str| {
+                let (numerator, denominator) = cancel(&parse(numerator).unwrap().to_expr(), &parse(denominator).unwrap().to_expr()).unwrap();
+                (numerator.to_str(), denominator.to_str())
+            };
+            // the gcd of each pair is (x + 1)/2 or (x + 1)/4, and only x + 1 is cancelled
+            assert_eq!(cancelled("x/2 + 1/2", "x + 1"), ("1".to_string(), "2".to_string()));
+            assert_eq!(cancelled("(x/2 + 1/2)*(x - 1)", "3*x + 3"), ("x - 1".to_string(), "6".to_string()));
+            assert_eq!(cancelled("x + 1", "x/4 + 1/4"), ("4".to_string(), "1".to_string()));
+            assert_eq!(cancelled("(2*x + 2)*y", "(x + 1)*(x - 2)"), ("2y".to_string(), "x - 2".to_string()));
+        }
+
+        #[test]
+        fn normalized_pair_scales_both_sides_alike() {
+            assert_eq!(Polynomial::normalized_pair(&polynomial("x/2"), &polynomial("-x/4 - 1/2")), (polynomial("-2*x"), polynomial("x + 2")));
+            assert_eq!(polynomial("-6*x^2 + 4").normalized(), polynomial("3*x^2 - 2"));
+        }
+
+        #[test]
+        fn nothing_cancels_against_zero() {
+            let cancelled = |numerator: &str, denominator: &str| cancel(&parse(numerator).unwrap().to_expr(), &parse(denominator).unwrap().to_expr());
+            assert_eq!(cancelled("1", "(sin(x) - sin(x))*y"), None);
+            assert_eq!(cancelled("x - x", "x + 1"), None);
+
+            let mut expr = parse("1/((sin(x)-sin(x))*y) + x").unwrap().to_expr();
+            expr.collapse();
+            let env = HashMap::from([(x(), 2.0), (VariableIdentifier::new("y"), 3.0)]);
+            assert_eq!(expr.evaluate(&env), Err(EvalError::DivisionByZero));
+        }
+
+        #[test]
+        fn cancelling_keeps_the_constant_factors() {
+            for input in ["((x + 1)*(1/4 + (x + 1)))/(x + 1)", "(x/2 + 1/2)*(x - 3)/(2*x + 2)", "(x^2/3 - 1/3)/((x - 1)^2*(x + 2))"] {
+                let expr = parse(input).unwrap().to_expr();
+                let mut collapsed = expr.clone();
+                collapsed.collapse();
+                for value in [-2.5, 0.3, 4.0] {
+                    let env = HashMap::from([(x(), value)]);
+                    let (expected, actual) = (expr.evaluate(&env).unwrap(), collapsed.evaluate(&env).unwrap());
+                    assert!((actual - expected).abs() < 1e-9 * expected.abs().max(1.0), "{} at {}: {}", input, value, collapsed.to_str());
+                }
+            }
+        }
+
+        #[test]
+        fn divide_gives_quotient_and_remainder() {
+            assert_eq!(polynomial("x^2 - y^2").divide(&polynomial("x - y")), (polynomial("x + y"), Polynomial::zero()));
+            assert_eq!(polynomial("x^2 + 1").divide(&polynomial("x + 1")), (polynomial("x - 1"), polynomial("2")));
+            assert_eq!(polynomial("x*y + y + 1").divide(&polynomial("2*y")), (polynomial("x/2 + 1/2"), polynomial("1")));
+        }
+    }
+}
@@ -0,0 +1,311 @@
+pub mod rational {
+    use std::{cmp::Ordering, fmt, ops::{Add, Div, Mul, Neg, Sub}, str::FromStr};
+    use crate::bigint::bigint::BigInt;
+
+    //---- Rational ----//
+    // An exact fraction, always kept in lowest terms with a positive denominator
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub struct Rational {
+        numerator: BigInt,
+        denominator: BigInt,
+    }
+
+    impl Rational {
+        // Panics on a zero denominator, as do recip and division by zero
+        pub fn new(numerator: impl Into<BigInt>, denominator: impl Into<BigInt>) -> Self {
+            Self::reduced(numerator.into(), denominator.into())
+        }
+
+        pub fn zero() -> Self {
+            Self { numerator: BigInt::zero(), denominator: BigInt::one() }
+        }
+
+        pub fn one() -> Self {
+            Self { numerator: BigInt::one(), denominator: BigInt::one() }
+        }
+
+        pub fn numerator(&self) -> &BigInt {
+            &self.numerator
+        }
+
+        pub fn denominator(&self) -> &BigInt {
+            &self.denominator
+        }
+
+        pub fn is_zero(&self) -> bool {
+            self.numerator.is_zero()
+        }
+
+        pub fn is_integer(&self) -> bool {
+            self.denominator.is_one()
+        }
+
+        pub fn is_positive(&self) -> bool {
+            self.numerator.is_positive()
+        }
+
+        pub fn is_negative(&self) -> bool {
+            self.numerator.is_negative()
+        }
+
+        pub fn abs(&self) -> Self {
+            Self { numerator: self.numerator.abs(), denominator: self.denominator.clone() }
+        }
+
+        pub fn recip(&self) -> Self {
+            Self::reduced(self.denominator.clone(), self.numerator.clone())
+        }
+
+        pub fn pow(&self, exponent: i32) -> Self {
+            let base = if exponent < 0 { self.recip() } else { self.clone() };
+            Self {
+                numerator: base.numerator.pow(exponent.unsigned_abs()),
+                denominator: base.denominator.pow(exponent.unsigned_abs()),
+            }
+        }
+
+        // The exact square root, when both parts are perfect squares
+        pub fn sqrt(&self) -> Option<Self> {
+            Some(Self { numerator: self.numerator.sqrt()?, denominator: self.denominator.sqrt()? })
+        }
+
+        // The integer value, when it is one and fits
+        pub fn to_i64(&self) -> Option<i64> {
+            if !self.is_integer() {
+                return None;
+            }
+            self.numerator.to_i64()
+        }
+
+        pub fn to_f64(&self) -> f64 {
+            // scale so that the integer quotient carries a full mantissa, even when both parts overflow f64
+            let shift = 64 + self.denominator.bits() as i64 - self.numerator.bits() as i64;
+            let scaled = if shift >= 0 { self.numerator.shifted_left(shift as u64) } else { self.numerator.shifted_right(shift.unsigned_abs()) };
+            let quotient = &scaled / &self.denominator;
+            quotient.to_f64() * 2f64.powi(-shift.clamp(i32::MIN.into(), i32::MAX.into()) as i32)
+        }
+
+        fn reduced(numerator: BigInt, denominator: BigInt) -> Self {
+            if denominator.is_zero() {
+                panic!("rational with a zero denominator");
+            }
+
+            let mut divisor = numerator.gcd(&denominator);
+            if denominator.is_negative() {
+                divisor = -divisor;
+            }
+            Self { numerator: &numerator / &divisor, denominator: &denominator / &divisor }
+        }
+    }
+
+    impl From<i32> for Rational {
+        fn from(value: i32) -> Self {
+            Self { numerator: value.into(), denominator: BigInt::one() }
+        }
+    }
+
+    impl From<i64> for Rational {
+        fn from(value: i64) -> Self {
+            Self { numerator: value.into(), denominator: BigInt::one() }
+        }
+    }
+
+    impl From<BigInt> for Rational {
+        fn from(value: BigInt) -> Self {
+            Self { numerator: value, denominator: BigInt::one() }
+        }
+    }
+
+    impl fmt::Display for Rational {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            if self.is_integer() {
+                write!(f, "{}", self.numerator)
+            }
+            else {
+                write!(f, "{}/{}", self.numerator, self.denominator)
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ParseRationalError;
+
+    // Accepts integers, decimals and fractions: `3`, `-0.25`, `3/2`
+    impl FromStr for Rational {
+        type Err = ParseRationalError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            if let Some((numerator, denominator)) = s.split_once('/') {
+                let numerator: Rational = numerator.parse()?;
+                let denominator: Rational = denominator.parse()?;
+                if denominator.is_zero() {
+                    return Err(ParseRationalError);
+                }
+                return Ok(numerator / denominator);
+            }
+
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, s),
+            };
+            let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+            if whole.starts_with('-') {
+                return Err(ParseRationalError);
+            }
+
+            let numerator: BigInt = format!("{}{}", whole, fraction).parse().map_err(|_| ParseRationalError)?;
+            let denominator = BigInt::from(10).pow(fraction.len() as u32);
+            let value = Self::reduced(numerator, denominator);
+            Ok(if negative { -value } else { value })
+        }
+    }
+    ////// Rational //////
+
+
+    //---- Arithmetic ----//
+    impl Add for &Rational {
+        type Output = Rational;
+
+        fn add(self, other: &Rational) -> Rational {
+            let numerator = &(&self.numerator * &other.denominator) + &(&other.numerator * &self.denominator);
+            Rational::reduced(numerator, &self.denominator * &other.denominator)
+        }
+    }
+
+    impl Sub for &Rational {
+        type Output = Rational;
+
+        fn sub(self, other: &Rational) -> Rational {
+            self + &-other
+        }
+    }
+
+    impl Mul for &Rational {
+        type Output = Rational;
+
+        fn mul(self, other: &Rational) -> Rational {
+            Rational::reduced(&self.numerator * &other.numerator, &self.denominator * &other.denominator)
+        }
+    }
+
+    impl Div for &Rational {
+        type Output = Rational;
+
+        fn div(self, other: &Rational) -> Rational {
+            Rational::reduced(&self.numerator * &other.denominator, &self.denominator * &other.numerator)
+        }
+    }
+
+    impl Neg for &Rational {
+        type Output = Rational;
+
+        fn neg(self) -> Rational {
+            Rational { numerator: -&self.numerator, denominator: self.denominator.clone() }
+        }
+    }
+
+    // owned operands forward to the borrowed implementations above
+    macro_rules! forward_owned {
+        ($($trait:ident $method:ident),*) => {$(
+            impl $trait for Rational {
+                type Output = Rational;
+
+                fn $method(self, other: Rational) -> Rational {
+                    (&self).$method(&other)
+                }
+            }
+        )*};
+    }
+    forward_owned!(Add add, Sub sub, Mul mul, Div div);
+
+    impl Neg for Rational {
+        type Output = Rational;
+
+        fn neg(self) -> Rational {
+            Rational { numerator: -self.numerator, denominator: self.denominator }
+        }
+    }
+
+    impl PartialOrd for Rational {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Rational {
+        fn cmp(&self, other: &Self) -> Ordering {
+            (&self.numerator * &other.denominator).cmp(&(&other.numerator * &self.denominator))
+        }
+    }
+    ////// Arithmetic //////
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn rational(s: &str) -> Rational {
+            s.parse().unwrap()
+        }
+
+        #[test]
+        fn fractions_reduce_to_lowest_terms() {
+            assert_eq!(Rational::new(6, 8), rational("3/4"));
+            assert_eq!(Rational::new(0, -5), Rational::zero());
+            assert_eq!(rational("-0.250"), Rational::new(-1, 4));
+            let scale = BigInt::from(10).pow(30);
+            let half = Rational::new(scale.clone(), &BigInt::from(2) * &scale);
+            assert_eq!((half.numerator().clone(), half.denominator().clone()), (BigInt::from(1), BigInt::from(2)));
+        }
+
+        #[test]
+        fn the_sign_goes_on_the_numerator() {
+            for (numerator, denominator, expected) in [(3, -4, "-3/4"), (-3, -4, "3/4"), (-3, 4, "-3/4")] {
+                let value = Rational::new(numerator, denominator);
+                assert_eq!(value.to_string(), expected);
+                assert!(value.denominator().is_positive());
+            }
+            assert!(Rational::new(1, -2).is_negative());
+            assert_eq!(Rational::new(-2, 3).recip(), Rational::new(3, -2));
+        }
+
+        #[test]
+        fn arithmetic_is_exact() {
+            assert_eq!(&rational("1/3") + &rational("1/6"), rational("1/2"));
+            assert_eq!(rational("1/3") - rational("1/2"), rational("-1/6"));
+            assert_eq!(rational("2/3") * rational("9/4"), rational("3/2"));
+            assert_eq!(rational("2/3") / rational("-4/9"), rational("-3/2"));
+            assert_eq!(-rational("5/7"), rational("-5/7"));
+            assert_eq!(&(&rational("1/10") + &rational("2/10")) - &rational("3/10"), Rational::zero());
+        }
+
+        #[test]
+        fn ordering_compares_values() {
+            let mut values = [rational("1/2"), rational("-3/4"), rational("2/3"), Rational::from(-1), Rational::zero()];
+            values.sort();
+            assert_eq!(values.map(|value| value.to_string()), ["-1", "-3/4", "0", "1/2", "2/3"]);
+            assert!(rational("1/3") > rational("333/1000"));
+            assert_eq!(rational("2/4").cmp(&rational("1/2")), Ordering::Equal);
+        }
+
+        #[test]
+        fn negative_powers_take_the_reciprocal() {
+            assert_eq!(rational("2/3").pow(3), rational("8/27"));
+            assert_eq!(rational("2/3").pow(-2), rational("9/4"));
+            assert_eq!(rational("-2/3").pow(-3), rational("-27/8"));
+            assert_eq!(rational("-5/7").pow(0), Rational::one());
+        }
+
+        #[test]
+        #[should_panic(expected = "zero denominator")]
+        fn a_zero_denominator_panics() {
+            Rational::new(1, 0);
+        }
+
+        #[test]
+        #[should_panic(expected = "zero denominator")]
+        fn zero_has_no_reciprocal() {
+            Rational::zero().pow(-1);
+        }
+    }
+}